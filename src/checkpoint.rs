@@ -0,0 +1,71 @@
+use crate::error::{Error, Result};
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub const DEFAULT_CHECKPOINT_FILE: &str = ".holograph-checkpoints.json";
+
+// Last block committed per network, persisted to disk so processing resumes where
+// it left off after a restart instead of at the chain head.
+pub struct Checkpoints {
+    path: PathBuf,
+    blocks: Mutex<HashMap<String, u64>>,
+}
+
+impl Checkpoints {
+    // Load checkpoints from `path`, starting empty if the file doesn't exist yet.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let blocks = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(Error::Storage(format!(
+                    "Failed to read checkpoint file {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(Checkpoints { path, blocks: Mutex::new(blocks) })
+    }
+
+    pub fn get(&self, network: &str) -> Option<u64> {
+        self.blocks.lock().unwrap_or_else(|e| e.into_inner()).get(network).copied()
+    }
+
+    // Move the checkpoint of a network to `block`. Blocks are committed in order, so
+    // this can also move the checkpoint back when a reorg re-processes blocks.
+    pub fn set(&self, network: &str, block: u64) {
+        self.blocks.lock().unwrap_or_else(|e| e.into_inner()).insert(network.to_string(), block);
+    }
+
+    // Write the checkpoints to disk, going through a temporary file so a crash
+    // mid-write never leaves a truncated file behind.
+    pub fn save(&self) -> Result<()> {
+        let contents = {
+            let blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+            serde_json::to_string_pretty(&*blocks)?
+        };
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, contents)
+            .and_then(|_| std::fs::rename(&tmp_path, &self.path))
+            .map_err(|e| {
+                Error::Storage(format!(
+                    "Failed to write checkpoint file {}: {}",
+                    self.path.display(),
+                    e
+                ))
+            })
+    }
+}
+
+impl Default for Checkpoints {
+    fn default() -> Self {
+        Checkpoints {
+            path: PathBuf::from(DEFAULT_CHECKPOINT_FILE),
+            blocks: Mutex::new(HashMap::new()),
+        }
+    }
+}
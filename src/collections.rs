@@ -0,0 +1,298 @@
+use crate::contracts::{call, ContractAbis};
+use crate::drops::DropSales;
+use crate::error::{Error, Result};
+use crate::events::EventType;
+use crate::providers::RpcProvider;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use ethers::abi::Abi;
+use ethers::contract::Contract;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_COLLECTIONS_FILE: &str = ".holograph-collections.json";
+
+// Holographable contracts are read from the registry in pages of this size
+const REGISTRY_PAGE_SIZE: u64 = 100;
+
+// What a holographable contract is, from the enforcer behind its holographer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionType {
+    Erc721,
+    Erc20,
+    Erc1155,
+    // A HolographDropERC721, an ERC721 sold through its own sale configuration.
+    Drop,
+    Generic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub collection_type: CollectionType,
+    // Sender of the deployment transaction. For a collection bridged in from another
+    // network this is the operator that executed the job.
+    pub deployer: Option<String>,
+    pub deploy_block: Option<u64>,
+    // Hash of the deployment config, as emitted in `BridgeableContractDeployed`.
+    pub deployment_hash: Option<String>,
+    pub transaction_hash: Option<String>,
+    // The HolographDropERC721 behind a drop's holographer, which emits its sale events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sales: Option<DropSales>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct NetworkCollections {
+    // Number of registry entries backfilled so far, the next backfill starts here.
+    registry_length: u64,
+    collections: BTreeMap<String, Collection>,
+}
+
+// Every holographable collection per network, keyed by lowercase address. The index is
+// backfilled from the registry and kept up to date from factory events, and persisted
+// to disk so a restart only backfills what is new.
+pub struct CollectionIndex {
+    path: PathBuf,
+    networks: Mutex<HashMap<String, NetworkCollections>>,
+    // Held while saving. Saves come from the block workers and the backfill at once,
+    // and would otherwise write the same temporary file, or rename an older snapshot
+    // over a newer one.
+    save_lock: Mutex<()>,
+}
+
+impl CollectionIndex {
+    // Load the index from `path`, starting empty if the file doesn't exist yet.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let networks = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(Error::Storage(format!(
+                    "Failed to read collection index {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(CollectionIndex { path, networks: Mutex::new(networks), save_lock: Mutex::new(()) })
+    }
+
+    fn networks(&self) -> std::sync::MutexGuard<'_, HashMap<String, NetworkCollections>> {
+        self.networks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn registry_length(&self, network: &str) -> u64 {
+        self.networks().get(network).map(|n| n.registry_length).unwrap_or_default()
+    }
+
+    pub fn contains(&self, network: &str, address: &str) -> bool {
+        self.networks().get(network).is_some_and(|n| n.collections.contains_key(address))
+    }
+
+    pub fn get(&self, network: &str, address: &str) -> Option<Collection> {
+        self.networks().get(network)?.collections.get(&address.to_lowercase()).cloned()
+    }
+
+    // Address of the collection whose source contract is `source`.
+    pub fn find_by_source(&self, network: &str, source: &str) -> Option<String> {
+        let source = source.to_lowercase();
+        self.networks()
+            .get(network)?
+            .collections
+            .iter()
+            .find(|(_, collection)| collection.source.as_deref() == Some(source.as_str()))
+            .map(|(address, _)| address.clone())
+    }
+
+    // Record drop events against a collection, returns false if it isn't indexed.
+    pub fn update_sales(
+        &self,
+        network: &str,
+        address: &str,
+        update: impl FnOnce(&mut DropSales),
+    ) -> bool {
+        let mut networks = self.networks();
+        let Some(collection) =
+            networks.get_mut(network).and_then(|n| n.collections.get_mut(address))
+        else {
+            return false;
+        };
+        update(collection.sales.get_or_insert_with(DropSales::default));
+        true
+    }
+
+    pub fn len(&self, network: &str) -> usize {
+        self.networks().get(network).map(|n| n.collections.len()).unwrap_or_default()
+    }
+
+    pub fn insert(&self, network: &str, address: &str, collection: Collection) {
+        self.networks()
+            .entry(network.to_string())
+            .or_default()
+            .collections
+            .insert(address.to_lowercase(), collection);
+    }
+
+    pub fn set_registry_length(&self, network: &str, length: u64) {
+        self.networks().entry(network.to_string()).or_default().registry_length = length;
+    }
+
+    // Write the index to disk, going through a temporary file like the checkpoints.
+    pub fn save(&self) -> Result<()> {
+        let _saving = self.save_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let contents = {
+            let networks = self.networks();
+            serde_json::to_string_pretty(&*networks)?
+        };
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, contents)
+            .and_then(|_| std::fs::rename(&tmp_path, &self.path))
+            .map_err(|e| {
+                Error::Storage(format!(
+                    "Failed to write collection index {}: {}",
+                    self.path.display(),
+                    e
+                ))
+            })
+    }
+}
+
+impl Default for CollectionIndex {
+    fn default() -> Self {
+        CollectionIndex {
+            path: PathBuf::from(DEFAULT_COLLECTIONS_FILE),
+            networks: Mutex::new(HashMap::new()),
+            save_lock: Mutex::new(()),
+        }
+    }
+}
+
+// Read the holographable contracts of the registry, starting at entry `from`.
+pub async fn holographable_contracts(
+    registry: &Contract<RpcProvider>,
+    from: u64,
+) -> Result<Vec<Address>> {
+    let length: U256 = call(registry, "getHolographableContractsLength").await?;
+    let mut contracts = Vec::new();
+    let mut index = U256::from(from);
+    while index < length {
+        let page: Vec<Address> = registry
+            .method("getHolographableContracts", (index, U256::from(REGISTRY_PAGE_SIZE)))?
+            .call()
+            .await?;
+        if page.is_empty() {
+            break;
+        }
+        index += U256::from(page.len());
+        contracts.extend(page);
+    }
+    Ok(contracts)
+}
+
+fn contract(
+    abi: &str,
+    address: Address,
+    provider: &Arc<RpcProvider>,
+) -> Result<Contract<RpcProvider>> {
+    let abi: Abi = serde_json::from_str(abi)?;
+    Ok(Contract::new(address, abi, provider.clone()))
+}
+
+// Tell what a collection is from the contract type of its holographer. Drops are
+// ERC721s whose source contract is a HolographDropERC721 proxy.
+pub async fn collection_type(
+    provider: &Arc<RpcProvider>,
+    abis: &ContractAbis,
+    address: Address,
+) -> Result<CollectionType> {
+    let holographer = contract(abis.holographer_abi, address, provider)?;
+    let contract_type: [u8; 32] = call(&holographer, "getContractType").await?;
+    let name = String::from_utf8_lossy(&contract_type);
+
+    Ok(match name.trim_start_matches('\0') {
+        "HolographERC721" => {
+            let source: Address = call(&holographer, "getSourceContract").await?;
+            let drop = contract(abis.holograph_drop_erc721_proxy_abi, source, provider)?;
+            // Other sources revert, or return nothing through their fallback
+            match call::<Address>(&drop, "getHolographDropERC721Source").await {
+                Ok(source) if !source.is_zero() => CollectionType::Drop,
+                Ok(_) => CollectionType::Erc721,
+                Err(Error::Contract(e))
+                    if e.is_revert() || matches!(e, ContractError::DecodingError(_)) =>
+                {
+                    CollectionType::Erc721
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        "HolographERC20" => CollectionType::Erc20,
+        "HolographERC1155" => CollectionType::Erc1155,
+        _ => CollectionType::Generic,
+    })
+}
+
+// The source contract behind a holographer, lowercased like the index keys.
+pub async fn source_contract(
+    provider: &Arc<RpcProvider>,
+    abis: &ContractAbis,
+    address: Address,
+) -> Result<String> {
+    let holographer = contract(abis.holographer_abi, address, provider)?;
+    let source: Address = call(&holographer, "getSourceContract").await?;
+    Ok(format!("{:?}", source))
+}
+
+// Build the index entry of a collection found in the registry, looking up its
+// `BridgeableContractDeployed` event in the block it was deployed in.
+pub async fn backfill_collection(
+    provider: &Arc<RpcProvider>,
+    abis: &ContractAbis,
+    factory: Address,
+    address: Address,
+) -> Result<Collection> {
+    let collection_type = collection_type(provider, abis, address).await?;
+    let holographer = contract(abis.holographer_abi, address, provider)?;
+    let deploy_block: U256 = call(&holographer, "getDeploymentBlock").await?;
+    let deploy_block = deploy_block.as_u64();
+
+    let mut collection = Collection {
+        collection_type,
+        deployer: None,
+        deploy_block: Some(deploy_block),
+        deployment_hash: None,
+        transaction_hash: None,
+        source: None,
+        sales: None,
+    };
+    if collection_type == CollectionType::Drop {
+        collection.source = Some(source_contract(provider, abis, address).await?);
+    }
+
+    let Some(topic) = EventType::BridgeableContractDeployed.sig_hash() else {
+        return Ok(collection);
+    };
+    let filter = Filter::new()
+        .address(factory)
+        .topic0(topic)
+        .topic1(H256::from(address))
+        .from_block(deploy_block)
+        .to_block(deploy_block);
+    if let Some(log) = provider.get_logs(&filter).await?.into_iter().next() {
+        collection.deployment_hash = log.topics.get(2).map(|hash| format!("{:?}", hash));
+        if let Some(transaction_hash) = log.transaction_hash {
+            collection.transaction_hash = Some(format!("{:?}", transaction_hash));
+            collection.deployer = provider
+                .get_transaction(transaction_hash)
+                .await?
+                .map(|transaction| format!("{:?}", transaction.from));
+        }
+    }
+    Ok(collection)
+}
@@ -0,0 +1,616 @@
+use crate::checkpoint::DEFAULT_CHECKPOINT_FILE;
+use crate::collections::DEFAULT_COLLECTIONS_FILE;
+use crate::contracts::ABI_ENVIRONMENTS;
+use crate::environment::Environment;
+use crate::error::{Error, Result};
+use crate::health::DEFAULT_STALL_THRESHOLD;
+use crate::logging::{LogFormat, LogLevel};
+use crate::networks;
+use crate::pipeline::{PipelineConfig, DEFAULT_BLOCK_QUEUE_SIZE, DEFAULT_BLOCK_WORKERS};
+use crate::reorg::BLOCK_HISTORY_WINDOW;
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::Address;
+use serde::Deserialize;
+
+pub const DEFAULT_CONFIG_FILE: &str = "holograph.toml";
+
+// What the operator does with the jobs it sees.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OperatorMode {
+    // Only watch and log jobs.
+    Listen,
+    // Ask before executing a job.
+    Manual,
+    // Execute jobs without asking.
+    Auto,
+}
+
+impl OperatorMode {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "listen" => Some(OperatorMode::Listen),
+            "manual" => Some(OperatorMode::Manual),
+            "auto" => Some(OperatorMode::Auto),
+            _ => None,
+        }
+    }
+}
+
+// Where the operator wallet comes from.
+#[derive(Debug, Clone)]
+pub enum WalletSource {
+    // No wallet, nothing is tracked as our own.
+    None,
+    // Watch only: our transactions are tracked but nothing can be signed.
+    Address(Address),
+    // A private key read from the named environment variable.
+    PrivateKeyEnv(String),
+    // An encrypted JSON keystore, with its password read from the named environment variable.
+    Keystore { path: PathBuf, password_env: String },
+}
+
+impl WalletSource {
+    // Load the wallet, if this source can sign.
+    pub fn signer(&self) -> Result<Option<LocalWallet>> {
+        match self {
+            WalletSource::None | WalletSource::Address(_) => Ok(None),
+            WalletSource::PrivateKeyEnv(var) => {
+                let key =
+                    std::env::var(var).map_err(|_| Error::Config(format!("{} is not set", var)))?;
+                let wallet = key
+                    .trim()
+                    .parse::<LocalWallet>()
+                    .map_err(|e| Error::Config(format!("Invalid private key in {}: {}", var, e)))?;
+                Ok(Some(wallet))
+            }
+            WalletSource::Keystore { path, password_env } => {
+                let password = std::env::var(password_env)
+                    .map_err(|_| Error::Config(format!("{} is not set", password_env)))?;
+                let wallet = LocalWallet::decrypt_keystore(path, password).map_err(|e| {
+                    Error::Config(format!("Failed to decrypt keystore {}: {}", path.display(), e))
+                })?;
+                Ok(Some(wallet))
+            }
+        }
+    }
+
+    // Address of the operator wallet, if one is configured.
+    pub fn address(&self) -> Result<Option<Address>> {
+        match self {
+            WalletSource::Address(address) => Ok(Some(*address)),
+            _ => Ok(self.signer()?.map(|wallet| wallet.address())),
+        }
+    }
+}
+
+// Limits applied to the transactions the operator sends.
+#[derive(Debug, Clone)]
+pub struct GasPolicy {
+    // Never send a transaction above this gas price.
+    pub max_gas_price_gwei: Option<u64>,
+    // Applied to the gas price estimated by the provider.
+    pub gas_price_multiplier: f64,
+    // Skip jobs that pay less than this over their gas cost.
+    pub min_profit_gwei: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    pub name: String,
+    pub rpc_urls: Vec<String>,
+    // None uses the default for the network, see `confirmations::ConfirmationPolicy`.
+    pub confirmations: Option<u64>,
+    pub pipeline: PipelineConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub environment: Environment,
+    pub operator_mode: OperatorMode,
+    pub networks: Vec<NetworkConfig>,
+    pub wallet: WalletSource,
+    pub gas: GasPolicy,
+    pub checkpoint_file: PathBuf,
+    pub collections_file: PathBuf,
+    // Address of the HTTP server for metrics and health checks, disabled when not set.
+    pub http_addr: Option<SocketAddr>,
+    pub readiness_stall: Duration,
+    pub log_format: LogFormat,
+    pub log_level: LogLevel,
+}
+
+// The configuration file as written, before defaults and overrides are applied.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    environment: Option<String>,
+    operator_mode: Option<String>,
+    networks: Vec<FileNetwork>,
+    wallet: FileWallet,
+    gas: FileGas,
+    storage: FileStorage,
+    metrics: FileMetrics,
+    logging: FileLogging,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileNetwork {
+    name: String,
+    rpc_urls: Vec<String>,
+    confirmations: Option<u64>,
+    block_workers: Option<usize>,
+    block_queue_size: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileWallet {
+    source: Option<String>,
+    address: Option<String>,
+    private_key_env: Option<String>,
+    keystore: Option<PathBuf>,
+    password_env: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileGas {
+    max_gas_price_gwei: Option<u64>,
+    gas_price_multiplier: Option<f64>,
+    min_profit_gwei: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileStorage {
+    checkpoint_file: Option<PathBuf>,
+    collections_file: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileMetrics {
+    http_addr: Option<String>,
+    readiness_stall_seconds: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileLogging {
+    format: Option<String>,
+    level: Option<String>,
+}
+
+// Read an environment variable, treating an empty value as unset.
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// Parse an override from the environment, recording an error if it is invalid.
+fn env_parse<T: FromStr>(name: &str, errors: &mut Vec<String>) -> Option<T> {
+    let value = env(name)?;
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            errors.push(format!("{} has an invalid value: {}", name, value));
+            None
+        }
+    }
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+}
+
+impl Config {
+    // Load the configuration file named by `HOLOGRAPH_CONFIG`, or `holograph.toml` if it
+    // exists, and apply the environment overrides on top of it:
+    //
+    // - `HOLOGRAPH_ENV`, `OPERATOR_MODE`
+    // - `NETWORKS`: comma separated networks, replacing those in the file
+    // - `<NETWORK>_PROVIDER_URLS`, or `PROVIDER_URL` for networks without URLs
+    // - `<NETWORK>_CONFIRMATIONS`, `<NETWORK>_BLOCK_WORKERS`, `<NETWORK>_BLOCK_QUEUE_SIZE`
+    // - `WALLET_SOURCE`, and `OPERATOR_ADDRESS` (or the older `TEST_ADDRESS`) for a
+    //   watch only wallet
+    // - `MAX_GAS_PRICE_GWEI`, `GAS_PRICE_MULTIPLIER`, `MIN_PROFIT_GWEI`
+    // - `CHECKPOINT_FILE`, `COLLECTIONS_FILE`
+    // - `HTTP_ADDR`, `READINESS_STALL_SECONDS`
+    // - `LOG_FORMAT`, `LOG_LEVEL`
+    //
+    // Every problem found is reported at once.
+    pub fn load() -> Result<Self> {
+        let (path, required) = match env("HOLOGRAPH_CONFIG") {
+            Some(path) => (PathBuf::from(path), true),
+            None => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+        };
+
+        let file = match std::fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).map_err(|e| {
+                Error::Config(format!("Invalid config file {}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => {
+                FileConfig::default()
+            }
+            Err(e) => {
+                return Err(Error::Config(format!(
+                    "Failed to read config file {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        let mut errors = Vec::new();
+        let config = Config::build(file, &mut errors);
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(Error::Config(format!(
+                "{} problem(s) found:\n  - {}",
+                errors.len(),
+                errors.join("\n  - ")
+            )))
+        }
+    }
+
+    fn build(file: FileConfig, errors: &mut Vec<String>) -> Self {
+        let environment_name =
+            env("HOLOGRAPH_ENV").or(file.environment).unwrap_or_else(|| "develop".to_string());
+        let environment = Environment::from_name(&environment_name).unwrap_or_else(|| {
+            errors.push(format!("Unsupported environment: {}", environment_name));
+            Environment::Develop
+        });
+        if !ABI_ENVIRONMENTS.contains(&environment.as_str()) {
+            errors.push(format!(
+                "No contract ABIs are available for environment {}, use one of: {}",
+                environment.as_str(),
+                ABI_ENVIRONMENTS.join(", ")
+            ));
+        }
+
+        let mode_name =
+            env("OPERATOR_MODE").or(file.operator_mode).unwrap_or_else(|| "listen".to_string());
+        let operator_mode = OperatorMode::from_name(&mode_name).unwrap_or_else(|| {
+            errors.push(format!("Unsupported operator mode: {}", mode_name));
+            OperatorMode::Listen
+        });
+
+        let networks = Self::build_networks(file.networks, errors);
+        // Everything indexes `networks[0]` as the primary network
+        if networks.is_empty() {
+            errors.push("No networks configured".to_string());
+        }
+        let wallet = Self::build_wallet(file.wallet, errors);
+
+        let gas = GasPolicy {
+            max_gas_price_gwei: env_parse("MAX_GAS_PRICE_GWEI", errors)
+                .or(file.gas.max_gas_price_gwei),
+            gas_price_multiplier: env_parse("GAS_PRICE_MULTIPLIER", errors)
+                .or(file.gas.gas_price_multiplier)
+                .unwrap_or(1.0),
+            min_profit_gwei: env_parse("MIN_PROFIT_GWEI", errors)
+                .or(file.gas.min_profit_gwei)
+                .unwrap_or(0),
+        };
+        if !gas.gas_price_multiplier.is_finite() || gas.gas_price_multiplier <= 0.0 {
+            errors.push(format!(
+                "gas_price_multiplier must be positive, got {}",
+                gas.gas_price_multiplier
+            ));
+        }
+
+        let checkpoint_file = env("CHECKPOINT_FILE")
+            .map(PathBuf::from)
+            .or(file.storage.checkpoint_file)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CHECKPOINT_FILE));
+        let collections_file = env("COLLECTIONS_FILE")
+            .map(PathBuf::from)
+            .or(file.storage.collections_file)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_COLLECTIONS_FILE));
+
+        let http_addr = env("HTTP_ADDR").or(file.metrics.http_addr).and_then(|addr| {
+            addr.parse::<SocketAddr>()
+                .map_err(|_| errors.push(format!("Invalid HTTP address: {}", addr)))
+                .ok()
+        });
+
+        let readiness_stall = env_parse("READINESS_STALL_SECONDS", errors)
+            .or(file.metrics.readiness_stall_seconds)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_STALL_THRESHOLD);
+        if readiness_stall.is_zero() {
+            errors.push("readiness_stall_seconds must be greater than 0".to_string());
+        }
+
+        let format_name =
+            env("LOG_FORMAT").or(file.logging.format).unwrap_or_else(|| "text".to_string());
+        let log_format = match format_name.as_str() {
+            "text" => LogFormat::Text,
+            "json" => LogFormat::Json,
+            _ => {
+                errors.push(format!("Unsupported log format: {}", format_name));
+                LogFormat::Text
+            }
+        };
+
+        let level_name =
+            env("LOG_LEVEL").or(file.logging.level).unwrap_or_else(|| "info".to_string());
+        let log_level = LogLevel::from_name(&level_name).unwrap_or_else(|| {
+            errors.push(format!("Unsupported log level: {}", level_name));
+            LogLevel::Info
+        });
+
+        Config {
+            environment,
+            operator_mode,
+            networks,
+            wallet,
+            gas,
+            checkpoint_file,
+            collections_file,
+            http_addr,
+            readiness_stall,
+            log_format,
+            log_level,
+        }
+    }
+
+    fn build_networks(
+        file_networks: Vec<FileNetwork>,
+        errors: &mut Vec<String>,
+    ) -> Vec<NetworkConfig> {
+        // The networks can be replaced as a whole from the environment
+        let file_networks = match env("NETWORKS") {
+            Some(names) => {
+                let mut file_networks = file_networks;
+                split_list(&names)
+                    .into_iter()
+                    .map(|name| {
+                        match file_networks.iter().position(|network| network.name == name) {
+                            Some(index) => file_networks.remove(index),
+                            None => FileNetwork { name, ..Default::default() },
+                        }
+                    })
+                    .collect()
+            }
+            None if file_networks.is_empty() => {
+                vec![FileNetwork { name: "optimism".to_string(), ..Default::default() }]
+            }
+            None => file_networks,
+        };
+
+        let mut seen = HashSet::new();
+        let mut networks = Vec::new();
+        for network in file_networks {
+            if network.name.is_empty() {
+                errors.push("A network is missing its name".to_string());
+                continue;
+            }
+            if !seen.insert(network.name.clone()) {
+                errors.push(format!("Network {} is configured more than once", network.name));
+                continue;
+            }
+            if networks::get(&network.name).is_none() {
+                errors.push(format!("Unknown network: {}", network.name));
+            }
+            let prefix = network.name.to_uppercase();
+
+            let mut rpc_urls = match env(&format!("{}_PROVIDER_URLS", prefix)) {
+                Some(urls) => split_list(&urls),
+                None => network.rpc_urls,
+            };
+            if rpc_urls.is_empty() {
+                rpc_urls = env("PROVIDER_URL").map(|urls| split_list(&urls)).unwrap_or_default();
+            }
+            if rpc_urls.is_empty() {
+                errors.push(format!(
+                    "Network {} has no RPC URLs, set rpc_urls, {}_PROVIDER_URLS or PROVIDER_URL",
+                    network.name, prefix
+                ));
+            }
+            for (index, url) in rpc_urls.iter().enumerate() {
+                if url.parse::<ethers::providers::Http>().is_err() {
+                    // Don't echo the URL, it may contain an API key
+                    errors.push(format!(
+                        "RPC URL #{} of {} is not a valid URL",
+                        index + 1,
+                        network.name
+                    ));
+                }
+            }
+
+            let confirmations =
+                env_parse(&format!("{}_CONFIRMATIONS", prefix), errors).or(network.confirmations);
+            // Blocks are only tracked for reorgs within the history window, so a block
+            // must become final before it leaves it
+            if confirmations
+                .is_some_and(|confirmations| confirmations >= BLOCK_HISTORY_WINDOW as u64)
+            {
+                errors.push(format!(
+                    "Network {} needs fewer than {} confirmations",
+                    network.name, BLOCK_HISTORY_WINDOW
+                ));
+            }
+
+            let workers = env_parse(&format!("{}_BLOCK_WORKERS", prefix), errors)
+                .or(network.block_workers)
+                .unwrap_or(DEFAULT_BLOCK_WORKERS);
+            let queue_size = env_parse(&format!("{}_BLOCK_QUEUE_SIZE", prefix), errors)
+                .or(network.block_queue_size)
+                .unwrap_or(DEFAULT_BLOCK_QUEUE_SIZE);
+            if workers == 0 || queue_size == 0 {
+                errors.push(format!(
+                    "Network {} needs at least one block worker and a queue size above 0",
+                    network.name
+                ));
+            }
+
+            networks.push(NetworkConfig {
+                name: network.name,
+                rpc_urls,
+                confirmations,
+                pipeline: PipelineConfig { workers, queue_size },
+            });
+        }
+        networks
+    }
+
+    fn build_wallet(file: FileWallet, errors: &mut Vec<String>) -> WalletSource {
+        // Without an explicit `WALLET_SOURCE`, an address in the environment means a watch
+        // only wallet
+        let env_address = env("OPERATOR_ADDRESS").or_else(|| env("TEST_ADDRESS"));
+        let source = match (env("WALLET_SOURCE"), &env_address, file.source) {
+            (Some(source), _, _) => source,
+            (None, Some(_), _) => "address".to_string(),
+            (None, None, Some(source)) => source,
+            (None, None, None) if file.address.is_some() => "address".to_string(),
+            (None, None, None) => "none".to_string(),
+        };
+
+        match source.as_str() {
+            "none" => WalletSource::None,
+            "address" => match env_address.or(file.address) {
+                Some(address) => match Address::from_str(&address) {
+                    Ok(address) => WalletSource::Address(address),
+                    Err(_) => {
+                        errors.push(format!("Invalid wallet address: {}", address));
+                        WalletSource::None
+                    }
+                },
+                None => {
+                    errors.push("Wallet source address needs a wallet address".to_string());
+                    WalletSource::None
+                }
+            },
+            "private_key_env" => {
+                let var = file.private_key_env.unwrap_or_else(|| "PRIVATE_KEY".to_string());
+                if env(&var).is_none() {
+                    errors.push(format!("Wallet private key variable {} is not set", var));
+                }
+                WalletSource::PrivateKeyEnv(var)
+            }
+            "keystore" => {
+                let password_env =
+                    file.password_env.unwrap_or_else(|| "KEYSTORE_PASSWORD".to_string());
+                if env(&password_env).is_none() {
+                    errors.push(format!("Keystore password variable {} is not set", password_env));
+                }
+                match file.keystore {
+                    Some(path) => {
+                        if !path.is_file() {
+                            errors.push(format!("Keystore {} does not exist", path.display()));
+                        }
+                        WalletSource::Keystore { path, password_env }
+                    }
+                    None => {
+                        errors.push("Wallet source keystore needs a keystore path".to_string());
+                        WalletSource::None
+                    }
+                }
+            }
+            _ => {
+                errors.push(format!("Unsupported wallet source: {}", source));
+                WalletSource::None
+            }
+        }
+    }
+
+    pub fn network_names(&self) -> Vec<String> {
+        self.networks.iter().map(|network| network.name.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(toml: &str) -> (Config, Vec<String>) {
+        let file: FileConfig = toml::from_str(toml).unwrap();
+        let mut errors = Vec::new();
+        let config = Config::build(file, &mut errors);
+        (config, errors)
+    }
+
+    #[test]
+    fn valid_config_builds_without_errors() {
+        let (config, errors) = build(
+            r#"
+            environment = "develop"
+
+            [[networks]]
+            name = "optimism"
+            rpc_urls = ["http://localhost:8545"]
+            confirmations = 5
+            "#,
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(config.environment, Environment::Develop);
+        assert_eq!(config.networks[0].confirmations, Some(5));
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_once() {
+        let (_, errors) = build(
+            r#"
+            environment = "staging"
+            operator_mode = "yolo"
+
+            [[networks]]
+            name = "nowhere"
+            rpc_urls = ["not a url"]
+            confirmations = 128
+
+            [[networks]]
+            name = "nowhere"
+
+            [logging]
+            format = "xml"
+            "#,
+        );
+        let expected = [
+            "Unsupported environment: staging",
+            "Unsupported operator mode: yolo",
+            "Unknown network: nowhere",
+            "RPC URL #1 of nowhere is not a valid URL",
+            "Network nowhere needs fewer than 128 confirmations",
+            "Network nowhere is configured more than once",
+            "Unsupported log format: xml",
+        ];
+        for message in expected {
+            assert!(errors.iter().any(|error| error == message), "{} in {:?}", message, errors);
+        }
+        assert_eq!(errors.len(), expected.len(), "{:?}", errors);
+    }
+
+    #[test]
+    fn environments_without_abis_are_rejected() {
+        let (_, errors) = build(
+            r#"
+            environment = "mainnet"
+
+            [[networks]]
+            name = "optimism"
+            rpc_urls = ["http://localhost:8545"]
+            "#,
+        );
+        assert_eq!(
+            errors,
+            ["No contract ABIs are available for environment mainnet, use one of: develop"]
+        );
+    }
+
+    #[test]
+    fn network_defaults_become_final_within_the_reorg_window() {
+        for network in networks::NETWORKS {
+            assert!(network.confirmations < BLOCK_HISTORY_WINDOW as u64, "{}", network.name);
+        }
+    }
+}
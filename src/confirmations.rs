@@ -0,0 +1,57 @@
+use crate::config::NetworkConfig;
+use crate::networks;
+
+use std::collections::HashMap;
+
+// Confirmations used for networks outside the networks table. This matches the
+// recent block window the block pipeline has always used.
+pub const DEFAULT_CONFIRMATIONS: u64 = 5;
+
+// Confirmations of a network without an explicit setting, from the networks table.
+fn network_default(network: &str) -> u64 {
+    networks::get(network).map_or(DEFAULT_CONFIRMATIONS, |network| network.confirmations)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BlockFinality {
+    // Not yet buried under enough blocks and may still be reorged out.
+    Provisional { confirmations: u64, required: u64 },
+    Final,
+}
+
+// Number of blocks that must be built on top of a block before the data indexed
+// from it, and any operator decision based on it, counts as final.
+#[derive(Default)]
+pub struct ConfirmationPolicy {
+    confirmations: HashMap<String, u64>,
+}
+
+impl ConfirmationPolicy {
+    // Build the policy for the configured networks, using the network default
+    // where no confirmations are set.
+    pub fn from_config(networks: &[NetworkConfig]) -> Self {
+        let confirmations = networks
+            .iter()
+            .map(|network| {
+                let required =
+                    network.confirmations.unwrap_or_else(|| network_default(&network.name));
+                (network.name.clone(), required)
+            })
+            .collect();
+        ConfirmationPolicy { confirmations }
+    }
+
+    pub fn required(&self, network: &str) -> u64 {
+        self.confirmations.get(network).copied().unwrap_or_else(|| network_default(network))
+    }
+
+    pub fn finality(&self, network: &str, block: u64, head: u64) -> BlockFinality {
+        let required = self.required(network);
+        let confirmations = head.saturating_sub(block);
+        if confirmations < required {
+            BlockFinality::Provisional { confirmations, required }
+        } else {
+            BlockFinality::Final
+        }
+    }
+}
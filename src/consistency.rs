@@ -0,0 +1,185 @@
+use crate::collections;
+use crate::config::{Config, NetworkConfig};
+use crate::contracts::{call, contract_type_key, get_abis, ContractAbis};
+use crate::error::{Error, Result};
+use crate::inspect::{self, Report};
+use crate::providers::{self, RpcProvider};
+use crate::LogMessage;
+
+use std::fmt;
+use std::sync::Arc;
+
+use ethers::abi::Abi;
+use ethers::contract::Contract;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use futures::stream::{self, StreamExt};
+use tokio::sync::mpsc;
+
+// Contract types known to the registry. Reserved types are the protocol's own
+// enforcers, the others are registered by projects.
+const CONTRACT_TYPES: &[&str] = &[
+    "HolographERC20",
+    "HolographERC721",
+    "HolographERC1155",
+    "HolographGeneric",
+    "HolographDropERC721",
+    "HolographRoyalties",
+    "CxipERC721",
+    "hToken",
+    "EditionsMetadataRenderer",
+    "DropsMetadataRenderer",
+    "DropsPriceOracleProxy",
+];
+
+// Bytecode is fetched for this many contracts at a time
+const CODE_FETCH_CONCURRENCY: usize = 16;
+
+// keccak256 of the code deployed at an address, if there is any.
+struct CodeHash(Option<H256>);
+
+impl fmt::Debug for CodeHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(hash) => write!(f, "{:?}", hash),
+            None => write!(f, "no code"),
+        }
+    }
+}
+
+async fn code_hash(provider: &RpcProvider, address: Address) -> Result<CodeHash> {
+    let code = provider.get_code(address, None).await?;
+    Ok(CodeHash((!code.is_empty()).then(|| H256::from(keccak256(&code)))))
+}
+
+// Record the address of a contract and the hash of its bytecode.
+async fn push_contract(
+    report: &mut Report,
+    provider: &RpcProvider,
+    name: &str,
+    address: Result<Address>,
+) {
+    report.push(name, &address);
+    if let Ok(address) = address {
+        report.push(format!("{} code", name), &code_hash(provider, address).await);
+    }
+}
+
+async fn check_network(
+    network: &NetworkConfig,
+    holograph_address: Address,
+    abis: &ContractAbis,
+    tx_logs: mpsc::Sender<LogMessage>,
+) -> Result<Report> {
+    let provider = Arc::new(providers::connect(&network.name, &network.rpc_urls, tx_logs)?);
+    let contract = |abi: &str, address: Address| -> Result<Contract<RpcProvider>> {
+        let abi: Abi = serde_json::from_str(abi)?;
+        Ok(Contract::new(address, abi, provider.clone()))
+    };
+
+    let mut report = Report::default();
+    push_contract(&mut report, &provider, "holograph", Ok(holograph_address)).await;
+
+    // Protocol contracts, behind their proxies
+    let holograph = contract(abis.holograph_abi, holograph_address)?;
+    for (name, getter) in [
+        ("bridge", "getBridge"),
+        ("factory", "getFactory"),
+        ("interfaces", "getInterfaces"),
+        ("operator", "getOperator"),
+        ("registry", "getRegistry"),
+        ("treasury", "getTreasury"),
+        ("utilityToken", "getUtilityToken"),
+    ] {
+        let address = call::<Address>(&holograph, getter).await;
+        push_contract(&mut report, &provider, name, address).await;
+    }
+
+    let registry_address: Address = call(&holograph, "getRegistry").await?;
+    let registry = contract(abis.holograph_registry_abi, registry_address)?;
+
+    // Enforcer and project contract types registered with the registry
+    for name in CONTRACT_TYPES {
+        let key = contract_type_key(name);
+        for (label, getter) in [
+            ("reserved type", "getReservedContractTypeAddress"),
+            ("type", "getContractTypeAddress"),
+        ] {
+            let address: Result<Address> =
+                async { Ok(registry.method::<_, Address>(getter, key)?.call().await?) }.await;
+            match address {
+                Ok(address) if address.is_zero() => {}
+                address => {
+                    let name = format!("{} {}", label, name);
+                    push_contract(&mut report, &provider, &name, address).await;
+                }
+            }
+        }
+    }
+
+    // Every collection deployed through the factory. A collection only exists on the
+    // networks it was deployed to, so only its bytecode is compared.
+    let collections = collections::holographable_contracts(&registry, 0).await?;
+    let hashes: Vec<(Address, Result<CodeHash>)> = stream::iter(collections)
+        .map(|address| {
+            let provider = provider.clone();
+            async move { (address, code_hash(&provider, address).await) }
+        })
+        .buffered(CODE_FETCH_CONCURRENCY)
+        .collect()
+        .await;
+    for (address, hash) in hashes {
+        report.push(format!("collection {:?} code", address), &hash);
+    }
+
+    Ok(report)
+}
+
+// `check`: compare the protocol contracts, registered contract types and collections
+// of every configured network, printing only what differs. Any difference is an error.
+pub async fn run(config: &Config) -> Result<()> {
+    if config.networks.len() < 2 {
+        return Err(Error::Config("check needs at least two networks".to_string()));
+    }
+    let abis = get_abis(config.environment.as_str());
+    let holograph_address = inspect::holograph_address(config)?;
+    let tx_logs = inspect::log_channel(config);
+
+    let checks = config
+        .networks
+        .iter()
+        .map(|network| check_network(network, holograph_address, &abis, tx_logs.clone()));
+    let results = futures::future::join_all(checks).await;
+
+    let mut reports = Vec::new();
+    let mut failed = Vec::new();
+    for (network, result) in config.networks.iter().zip(results) {
+        match result {
+            Ok(report) => reports.push((network.name.as_str(), report)),
+            Err(e) => {
+                eprintln!("Failed to check {} [{}]: {}", network.name, e.kind(), e);
+                failed.push(network.name.as_str());
+            }
+        }
+    }
+
+    // Compare what could be checked, but a network that wasn't checked can't pass
+    let differences = if reports.len() >= 2 { inspect::print_reports(&reports, true) } else { 0 };
+    if !failed.is_empty() {
+        return Err(Error::NotFound(format!(
+            "{} of {} networks could not be checked: {}",
+            failed.len(),
+            config.networks.len(),
+            failed.join(", ")
+        )));
+    }
+    // Fail so scripts and CI can tell the deployments apart
+    if differences > 0 {
+        return Err(Error::Inconsistent(format!(
+            "{} difference(s) across {} networks",
+            differences,
+            reports.len()
+        )));
+    }
+    Ok(())
+}
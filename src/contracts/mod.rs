@@ -1,45 +1,80 @@
 use crate::environment::Environment;
+use crate::error::Result;
+use crate::providers::RpcProvider;
+use ethers::abi::Detokenize;
+use ethers::contract::Contract;
 use ethers::types::Address;
 use std::collections::HashMap;
 use std::str::FromStr;
 
 pub struct ContractAbis {
     pub cxip_erc721_abi: &'static str,
+    pub drops_price_oracle_abi: &'static str,
     pub faucet_abi: &'static str,
     pub holograph_abi: &'static str,
     pub holograph_bridge_abi: &'static str,
+    pub holograph_bridge_proxy_abi: &'static str,
     pub holograph_drop_erc721_abi: &'static str,
+    pub holograph_drop_erc721_proxy_abi: &'static str,
     pub holograph_erc20_abi: &'static str,
     pub holograph_erc721_abi: &'static str,
     pub holograph_factory_abi: &'static str,
+    pub holograph_factory_proxy_abi: &'static str,
     pub holograph_interfaces_abi: &'static str,
     pub holograph_operator_abi: &'static str,
+    pub holograph_operator_proxy_abi: &'static str,
     pub holograph_registry_abi: &'static str,
+    pub holograph_registry_proxy_abi: &'static str,
+    pub holograph_treasury_abi: &'static str,
+    pub holograph_treasury_proxy_abi: &'static str,
     pub holographer_abi: &'static str,
     pub layer_zero_abi: &'static str,
+    pub layer_zero_module_abi: &'static str,
     pub mock_lz_endpoint_abi: &'static str,
     pub editions_metadata_renderer_abi: &'static str,
     pub owner_abi: &'static str,
 }
 
+// Environments with bundled ABIs, `get_abis` panics for any other.
+pub const ABI_ENVIRONMENTS: &[&str] = &["develop"];
+
 fn abi_path(environment: &str, contract: &str) -> &'static str {
     match environment {
         "develop" => match contract {
             "CxipERC721" => include_str!("../../abis/develop/CxipERC721.json"),
+            "IDropsPriceOracle" => include_str!("../../abis/develop/IDropsPriceOracle.json"),
             "Faucet" => include_str!("../../abis/develop/Faucet.json"),
             "Holograph" => include_str!("../../abis/develop/Holograph.json"),
             "HolographBridge" => include_str!("../../abis/develop/HolographBridge.json"),
+            "HolographBridgeProxy" => include_str!("../../abis/develop/HolographBridgeProxy.json"),
             "HolographDropERC721" => include_str!("../../abis/develop/HolographDropERC721.json"),
+            "HolographDropERC721Proxy" => {
+                include_str!("../../abis/develop/HolographDropERC721Proxy.json")
+            }
             "HolographERC20" => include_str!("../../abis/develop/HolographERC20.json"),
             "HolographERC721" => include_str!("../../abis/develop/HolographERC721.json"),
             "HolographFactory" => include_str!("../../abis/develop/HolographFactory.json"),
+            "HolographFactoryProxy" => {
+                include_str!("../../abis/develop/HolographFactoryProxy.json")
+            }
             "HolographInterfaces" => include_str!("../../abis/develop/HolographInterfaces.json"),
             "HolographOperator" => include_str!("../../abis/develop/HolographOperator.json"),
+            "HolographOperatorProxy" => {
+                include_str!("../../abis/develop/HolographOperatorProxy.json")
+            }
             "HolographRegistry" => include_str!("../../abis/develop/HolographRegistry.json"),
+            "HolographRegistryProxy" => {
+                include_str!("../../abis/develop/HolographRegistryProxy.json")
+            }
+            "HolographTreasury" => include_str!("../../abis/develop/HolographTreasury.json"),
+            "HolographTreasuryProxy" => {
+                include_str!("../../abis/develop/HolographTreasuryProxy.json")
+            }
             "Holographer" => include_str!("../../abis/develop/Holographer.json"),
             "LayerZeroEndpointInterface" => {
                 include_str!("../../abis/develop/LayerZeroEndpointInterface.json")
             }
+            "LayerZeroModule" => include_str!("../../abis/develop/LayerZeroModule.json"),
             "MockLZEndpoint" => include_str!("../../abis/develop/MockLZEndpoint.json"),
             "EditionsMetadataRenderer" => {
                 include_str!("../../abis/develop/EditionsMetadataRenderer.json")
@@ -56,24 +91,45 @@ fn abi_path(environment: &str, contract: &str) -> &'static str {
 pub fn get_abis(environment: &str) -> ContractAbis {
     ContractAbis {
         cxip_erc721_abi: abi_path(environment, "CxipERC721"),
+        drops_price_oracle_abi: abi_path(environment, "IDropsPriceOracle"),
         faucet_abi: abi_path(environment, "Faucet"),
         holograph_abi: abi_path(environment, "Holograph"),
         holograph_bridge_abi: abi_path(environment, "HolographBridge"),
+        holograph_bridge_proxy_abi: abi_path(environment, "HolographBridgeProxy"),
         holograph_drop_erc721_abi: abi_path(environment, "HolographDropERC721"),
+        holograph_drop_erc721_proxy_abi: abi_path(environment, "HolographDropERC721Proxy"),
         holograph_erc20_abi: abi_path(environment, "HolographERC20"),
         holograph_erc721_abi: abi_path(environment, "HolographERC721"),
         holograph_factory_abi: abi_path(environment, "HolographFactory"),
+        holograph_factory_proxy_abi: abi_path(environment, "HolographFactoryProxy"),
         holograph_interfaces_abi: abi_path(environment, "HolographInterfaces"),
         holograph_operator_abi: abi_path(environment, "HolographOperator"),
+        holograph_operator_proxy_abi: abi_path(environment, "HolographOperatorProxy"),
         holograph_registry_abi: abi_path(environment, "HolographRegistry"),
+        holograph_registry_proxy_abi: abi_path(environment, "HolographRegistryProxy"),
+        holograph_treasury_abi: abi_path(environment, "HolographTreasury"),
+        holograph_treasury_proxy_abi: abi_path(environment, "HolographTreasuryProxy"),
         holographer_abi: abi_path(environment, "Holographer"),
         layer_zero_abi: abi_path(environment, "LayerZeroEndpointInterface"),
+        layer_zero_module_abi: abi_path(environment, "LayerZeroModule"),
         mock_lz_endpoint_abi: abi_path(environment, "MockLZEndpoint"),
         editions_metadata_renderer_abi: abi_path(environment, "EditionsMetadataRenderer"),
         owner_abi: abi_path(environment, "Owner"),
     }
 }
 
+// Call a view function without arguments.
+pub async fn call<T: Detokenize>(contract: &Contract<RpcProvider>, method: &str) -> Result<T> {
+    Ok(contract.method::<_, T>(method, ())?.call().await?)
+}
+
+// Registry key of a contract type: its name, right aligned in 32 bytes.
+pub fn contract_type_key(name: &str) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key[32 - name.len()..].copy_from_slice(name.as_bytes());
+    key
+}
+
 pub fn holograph_addresses() -> HashMap<Environment, Address> {
     let mut m = HashMap::new();
     m.insert(
@@ -0,0 +1,548 @@
+use crate::config::{Config, NetworkConfig};
+use crate::contracts::{call, contract_type_key, get_abis, ContractAbis};
+use crate::error::{Error, Result};
+use crate::events::{decode_log, DecodedEvent};
+use crate::inspect;
+use crate::networks;
+use crate::providers::{self, RpcProvider};
+use crate::transactions;
+use crate::LogMessage;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use colored::*;
+use ethers::abi::{self, Abi, Token};
+use ethers::contract::Contract;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::{format_ether, get_create2_address_from_hash, hex, keccak256};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+// Gas limit of the deployment job on other networks, unless the spec sets one
+const DEFAULT_BRIDGE_GAS_LIMIT: u64 = 5_000_000;
+
+// `HolographERC721Event`s a drop needs to enforce its sale and royalties:
+// beforeSafeTransfer, beforeTransfer, onIsApprovedForAll and customContractURI.
+const DROP_EVENTS: &[u32] = &[12, 14, 17, 18];
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum DeployType {
+    Erc721,
+    Erc20,
+    Drop,
+}
+
+// What to deploy and where, as written in the spec file passed to `deploy`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    #[serde(rename = "type")]
+    deploy_type: DeployType,
+    // Network the deployment is sent on.
+    network: String,
+    // Networks the same collection is deployed to through the bridge.
+    #[serde(default)]
+    also_on: Vec<String>,
+    // Reusing a salt gives the same address, it defaults to the current time.
+    salt: Option<H256>,
+    // Creation code of the source contract, and of the holographer to predict the
+    // address. Either a compiled artifact with a `bytecode` field or a hex file.
+    source_bytecode: PathBuf,
+    holographer_bytecode: PathBuf,
+    name: String,
+    symbol: String,
+    #[serde(default)]
+    royalty_bps: u16,
+    // Bitmap of the enforcer events forwarded to the source contract.
+    event_config: Option<U256>,
+    // Owner of the source contract, the signer by default.
+    owner: Option<Address>,
+    // Init code of the source contract, for sources we don't know how to initialize.
+    source_init: Option<Bytes>,
+    bridge_gas_limit: Option<u64>,
+    #[serde(default)]
+    erc20: Erc20Spec,
+    #[serde(default)]
+    drop: DropSpec,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Erc20Spec {
+    decimals: u8,
+    // EIP-712 domain, defaulting to the token name.
+    domain_name: Option<String>,
+    domain_version: String,
+}
+
+impl Default for Erc20Spec {
+    fn default() -> Self {
+        Erc20Spec { decimals: 18, domain_name: None, domain_version: "1".to_string() }
+    }
+}
+
+// `DropsInitializer` and `SalesConfiguration` of a HolographDropERC721.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct DropSpec {
+    erc721_transfer_helper: Address,
+    market_filter_address: Address,
+    // Where sale proceeds go, the owner by default.
+    funds_recipient: Option<Address>,
+    // Number of editions, 0 for an open edition.
+    edition_size: u64,
+    enable_opensea_royalty_registry: bool,
+    // Public sale price in USD with 6 decimals, converted with the price oracle.
+    public_sale_price: u64,
+    max_sale_purchase_per_address: u32,
+    public_sale_start: u64,
+    public_sale_end: u64,
+    presale_start: u64,
+    presale_end: u64,
+    presale_merkle_root: H256,
+    // The registry's EditionsMetadataRenderer unless set.
+    metadata_renderer: Option<Address>,
+    description: String,
+    image_uri: String,
+    animation_uri: String,
+}
+
+// `DeploymentConfig` of the factory.
+struct DeploymentConfig {
+    contract_type: [u8; 32],
+    chain_type: u32,
+    salt: H256,
+    byte_code: Bytes,
+    init_code: Bytes,
+}
+
+impl DeploymentConfig {
+    // The hash the signer signs, which is also the CREATE2 salt of the holographer.
+    fn hash(&self, signer: Address) -> H256 {
+        let mut packed = Vec::with_capacity(32 * 5 + 4 + 20);
+        packed.extend_from_slice(&self.contract_type);
+        packed.extend_from_slice(&self.chain_type.to_be_bytes());
+        packed.extend_from_slice(self.salt.as_bytes());
+        packed.extend_from_slice(&keccak256(&self.byte_code));
+        packed.extend_from_slice(&keccak256(&self.init_code));
+        packed.extend_from_slice(signer.as_bytes());
+        H256::from(keccak256(packed))
+    }
+
+    fn token(&self) -> Token {
+        Token::Tuple(vec![
+            Token::FixedBytes(self.contract_type.to_vec()),
+            Token::Uint(self.chain_type.into()),
+            Token::FixedBytes(self.salt.as_bytes().to_vec()),
+            Token::Bytes(self.byte_code.to_vec()),
+            Token::Bytes(self.init_code.to_vec()),
+        ])
+    }
+}
+
+// Read creation code from a compiled artifact or from a file holding only hex.
+fn read_bytecode(path: &Path) -> Result<Bytes> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::Config(format!("Failed to read {}: {}", path.display(), e)))?;
+    let code = match serde_json::from_str::<serde_json::Value>(&contents) {
+        Ok(artifact) => match &artifact["bytecode"] {
+            serde_json::Value::String(code) => code.clone(),
+            bytecode => bytecode["object"].as_str().unwrap_or_default().to_string(),
+        },
+        Err(_) => contents.trim().to_string(),
+    };
+    let code = hex::decode(code.trim_start_matches("0x"))
+        .map_err(|e| Error::Config(format!("Invalid bytecode in {}: {}", path.display(), e)))?;
+    if code.is_empty() {
+        return Err(Error::Config(format!("No bytecode in {}", path.display())));
+    }
+    Ok(code.into())
+}
+
+fn events_bitmap(events: &[u32]) -> U256 {
+    events.iter().fold(U256::zero(), |bitmap, event| bitmap | (U256::one() << *event))
+}
+
+// Contracts of the Holograph deployment on one network.
+struct Deployment {
+    network: NetworkConfig,
+    holograph_id: u32,
+    provider: Arc<RpcProvider>,
+    factory: Contract<RpcProvider>,
+    registry: Contract<RpcProvider>,
+    bridge: Contract<RpcProvider>,
+}
+
+async fn connect(
+    config: &Config,
+    name: &str,
+    abis: &ContractAbis,
+    tx_logs: mpsc::Sender<LogMessage>,
+) -> Result<Deployment> {
+    let network = config
+        .networks
+        .iter()
+        .find(|network| network.name == name)
+        .ok_or_else(|| Error::Config(format!("Network {} is not configured", name)))?;
+    let holograph_id = networks::get(name)
+        .ok_or_else(|| Error::Config(format!("Unknown network: {}", name)))?
+        .holograph_id;
+    let provider = Arc::new(providers::connect(&network.name, &network.rpc_urls, tx_logs)?);
+    let contract = |abi: &str, address: Address| -> Result<Contract<RpcProvider>> {
+        let abi: Abi = serde_json::from_str(abi)?;
+        Ok(Contract::new(address, abi, provider.clone()))
+    };
+
+    let holograph = contract(abis.holograph_abi, inspect::holograph_address(config)?)?;
+    let factory = contract(abis.holograph_factory_abi, call(&holograph, "getFactory").await?)?;
+    let registry = contract(abis.holograph_registry_abi, call(&holograph, "getRegistry").await?)?;
+    let bridge = contract(abis.holograph_bridge_abi, call(&holograph, "getBridge").await?)?;
+    Ok(Deployment { network: network.clone(), holograph_id, provider, factory, registry, bridge })
+}
+
+// Build the deployment config of the spec. Source contracts are initialized through
+// their proxy, with the registry of the network the deployment is sent on.
+async fn deployment_config(
+    spec: &Spec,
+    origin: &Deployment,
+    owner: Address,
+) -> Result<DeploymentConfig> {
+    let registry = origin.registry.address();
+    let source_init = |source_type: &str, init: Vec<u8>| {
+        abi::encode(&[
+            Token::FixedBytes(contract_type_key(source_type).to_vec()),
+            Token::Address(registry),
+            Token::Bytes(init),
+        ])
+    };
+
+    let (contract_type, init_code) = match spec.deploy_type {
+        DeployType::Erc721 | DeployType::Drop => {
+            let (source_init, default_events) = match (&spec.source_init, spec.deploy_type) {
+                (Some(init), _) => (init.to_vec(), U256::zero()),
+                (None, DeployType::Drop) => {
+                    let init = drop_init(spec, origin, owner).await?;
+                    (source_init("HolographDropERC721", init), events_bitmap(DROP_EVENTS))
+                }
+                (None, _) => {
+                    (source_init("CxipERC721", abi::encode(&[Token::Address(owner)])), U256::zero())
+                }
+            };
+            let init = abi::encode(&[
+                Token::String(spec.name.clone()),
+                Token::String(spec.symbol.clone()),
+                Token::Uint(spec.royalty_bps.into()),
+                Token::Uint(spec.event_config.unwrap_or(default_events)),
+                Token::Bool(false),
+                Token::Bytes(source_init),
+            ]);
+            ("HolographERC721", init)
+        }
+        DeployType::Erc20 => {
+            let init = abi::encode(&[
+                Token::String(spec.name.clone()),
+                Token::String(spec.symbol.clone()),
+                Token::Uint(spec.erc20.decimals.into()),
+                Token::Uint(spec.event_config.unwrap_or_default()),
+                Token::String(spec.erc20.domain_name.clone().unwrap_or_else(|| spec.name.clone())),
+                Token::String(spec.erc20.domain_version.clone()),
+                Token::Bool(false),
+                Token::Bytes(spec.source_init.clone().unwrap_or_default().to_vec()),
+            ]);
+            ("HolographERC20", init)
+        }
+    };
+
+    let salt = match spec.salt {
+        Some(salt) => salt,
+        None => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            H256::from_low_u64_be(now)
+        }
+    };
+    Ok(DeploymentConfig {
+        contract_type: contract_type_key(contract_type),
+        chain_type: origin.holograph_id,
+        salt,
+        byte_code: read_bytecode(&spec.source_bytecode)?,
+        init_code: init_code.into(),
+    })
+}
+
+// `DropsInitializer` of a HolographDropERC721, with the metadata of the renderer.
+async fn drop_init(spec: &Spec, origin: &Deployment, owner: Address) -> Result<Vec<u8>> {
+    let drop = &spec.drop;
+    let renderer = match drop.metadata_renderer {
+        Some(renderer) => renderer,
+        None => {
+            let key = contract_type_key("EditionsMetadataRenderer");
+            let renderer: Address =
+                origin.registry.method("getContractTypeAddress", key)?.call().await?;
+            if renderer.is_zero() {
+                return Err(Error::NotFound(format!(
+                    "No EditionsMetadataRenderer registered on {}",
+                    origin.network.name
+                )));
+            }
+            renderer
+        }
+    };
+    let renderer_init = abi::encode(&[
+        Token::String(drop.description.clone()),
+        Token::String(drop.image_uri.clone()),
+        Token::String(drop.animation_uri.clone()),
+    ]);
+    let sales_config = Token::Tuple(vec![
+        Token::Uint(drop.public_sale_price.into()),
+        Token::Uint(drop.max_sale_purchase_per_address.into()),
+        Token::Uint(drop.public_sale_start.into()),
+        Token::Uint(drop.public_sale_end.into()),
+        Token::Uint(drop.presale_start.into()),
+        Token::Uint(drop.presale_end.into()),
+        Token::FixedBytes(drop.presale_merkle_root.as_bytes().to_vec()),
+    ]);
+    Ok(abi::encode(&[Token::Tuple(vec![
+        Token::Address(drop.erc721_transfer_helper),
+        Token::Address(drop.market_filter_address),
+        Token::Address(owner),
+        Token::Address(drop.funds_recipient.unwrap_or(owner)),
+        Token::Uint(drop.edition_size.into()),
+        Token::Uint(spec.royalty_bps.into()),
+        Token::Bool(drop.enable_opensea_royalty_registry),
+        sales_config,
+        Token::Address(renderer),
+        Token::Bytes(renderer_init),
+    ])]))
+}
+
+// `BridgeSettings` of a deployment on another network, paying the bridge fees and
+// the operator's gas on the destination.
+async fn bridge_settings(
+    config: &Config,
+    origin: &Deployment,
+    destination: &Deployment,
+    gas_limit: U256,
+    bridge_payload: Vec<u8>,
+) -> Result<Token> {
+    let gas_price = transactions::gas_price(&destination.provider, &config.gas).await?;
+    let to_chain = destination.holograph_id;
+    let payload: Bytes = origin
+        .bridge
+        .method(
+            "getBridgeOutRequestPayload",
+            (to_chain, origin.factory.address(), gas_limit, gas_price, Bytes::from(bridge_payload)),
+        )?
+        .from(transactions::signer(config)?.address())
+        .call()
+        .await?;
+    let (hlg_fee, msg_fee, _dst_gas_price): (U256, U256, U256) = origin
+        .bridge
+        .method("getMessageFee", (to_chain, gas_limit, gas_price, payload))?
+        .call()
+        .await?;
+    Ok(Token::Tuple(vec![
+        Token::Uint(hlg_fee + msg_fee),
+        Token::Uint(gas_limit),
+        Token::Uint(gas_price),
+        Token::Uint(to_chain.into()),
+    ]))
+}
+
+// `deploy <spec.toml> [--dry-run]`: sign a deployment config and deploy it through
+// the factory, on the spec's network and on every network in `also_on` it isn't
+// deployed to yet.
+pub async fn run(config: &Config, args: &[String]) -> Result<()> {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let spec_path = args.iter().find(|arg| !arg.starts_with("--")).ok_or_else(|| {
+        Error::Config("Usage: holograph-rs deploy <spec.toml> [--dry-run]".to_string())
+    })?;
+    let spec: Spec = std::fs::read_to_string(spec_path)
+        .map_err(|e| Error::Config(format!("Failed to read {}: {}", spec_path, e)))
+        .and_then(|contents| {
+            toml::from_str(&contents)
+                .map_err(|e| Error::Config(format!("Invalid deploy spec {}: {}", spec_path, e)))
+        })?;
+
+    let wallet = transactions::signer(config)?;
+    let signer = wallet.address();
+    let abis = get_abis(config.environment.as_str());
+    let tx_logs = inspect::log_channel(config);
+
+    let origin = connect(config, &spec.network, &abis, tx_logs.clone()).await?;
+    let mut destinations = Vec::new();
+    for name in spec.also_on.iter().filter(|name| **name != spec.network) {
+        destinations.push(connect(config, name, &abis, tx_logs.clone()).await?);
+    }
+
+    let deployment = deployment_config(&spec, &origin, spec.owner.unwrap_or(signer)).await?;
+    let hash = deployment.hash(signer);
+    let holographer_code = read_bytecode(&spec.holographer_bytecode)?;
+    let address =
+        get_create2_address_from_hash(origin.factory.address(), hash, keccak256(&holographer_code));
+    println!("{:<20} {:?}", "deployment hash", hash);
+    println!("{:<20} {:?}", "salt", deployment.salt);
+    println!("{:<20} {:?}", "signer", signer);
+    println!("{:<20} {:?}", "address", address);
+
+    // Networks the config is already deployed on are skipped
+    let mut pending = Vec::new();
+    for target in std::iter::once(&origin).chain(&destinations) {
+        let deployed: bool =
+            target.registry.method("isHolographedHashDeployed", hash)?.call().await?;
+        if deployed {
+            let existing: Address =
+                target.registry.method("getHolographedHashAddress", hash)?.call().await?;
+            println!("{:<20} {} {:?}", target.network.name, "already deployed".yellow(), existing);
+        } else {
+            println!("{:<20} {}", target.network.name, "to deploy".green());
+            pending.push(target);
+        }
+    }
+    if pending.is_empty() {
+        println!("\nNothing to deploy");
+        return Ok(());
+    }
+
+    let signature = wallet
+        .sign_message(hash.as_bytes())
+        .await
+        .map_err(|e| Error::Transaction(format!("Failed to sign deployment config: {}", e)))?;
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    signature.r.to_big_endian(&mut r);
+    signature.s.to_big_endian(&mut s);
+    let signature = Token::Tuple(vec![
+        Token::FixedBytes(r.to_vec()),
+        Token::FixedBytes(s.to_vec()),
+        Token::Uint(signature.v.into()),
+    ]);
+
+    let (on_origin, bridged): (Vec<&Deployment>, Vec<&Deployment>) =
+        pending.into_iter().partition(|target| target.network.name == origin.network.name);
+    let tx: TypedTransaction = if bridged.is_empty() {
+        let calldata = origin
+            .factory
+            .method::<_, ()>(
+                "deployHolographableContract",
+                (deployment.token(), signature, signer),
+            )?
+            .calldata()
+            .ok_or_else(|| {
+                Error::Decode("Failed to encode deployHolographableContract".to_string())
+            })?;
+        TransactionRequest::new().to(origin.factory.address()).data(calldata).into()
+    } else {
+        let gas_limit = U256::from(spec.bridge_gas_limit.unwrap_or(DEFAULT_BRIDGE_GAS_LIMIT));
+        let bridge_payload =
+            abi::encode(&[deployment.token(), signature.clone(), Token::Address(signer)]);
+        let mut settings = Vec::new();
+        let mut value = U256::zero();
+        for destination in bridged {
+            let setting =
+                bridge_settings(config, &origin, destination, gas_limit, bridge_payload.clone())
+                    .await?;
+            if let Token::Tuple(fields) = &setting {
+                value += fields[0].clone().into_uint().unwrap_or_default();
+            }
+            settings.push(setting);
+        }
+        println!("{:<20} {} {}", "bridge fees", format_ether(value), native_token(&origin));
+        let calldata = origin
+            .factory
+            .method::<_, ()>(
+                "deployHolographableContractMultiChain",
+                (
+                    deployment.token(),
+                    signature,
+                    signer,
+                    !on_origin.is_empty(),
+                    Token::Array(settings),
+                ),
+            )?
+            .calldata()
+            .ok_or_else(|| {
+                Error::Decode("Failed to encode deployHolographableContractMultiChain".to_string())
+            })?;
+        TransactionRequest::new().to(origin.factory.address()).data(calldata).value(value).into()
+    };
+
+    if dry_run {
+        println!("\nDry run, nothing sent");
+        return Ok(());
+    }
+    let receipt = transactions::send(&origin.provider, &wallet, tx, &config.gas).await?;
+    println!("\n{} in block {}", "Deployed".green(), receipt.block_number.unwrap_or_default());
+
+    // Other networks deploy when the bridge delivers the job, only the origin can be
+    // checked here
+    if !on_origin.is_empty() {
+        let deployed = deployed_address(&origin, &receipt, hash).await?;
+        if deployed != address {
+            return Err(Error::Transaction(format!(
+                "Deployed to {:?} instead of the predicted {:?}",
+                deployed, address
+            )));
+        }
+        println!("{:<20} {:?}", "verified", deployed);
+    }
+    Ok(())
+}
+
+// Address the factory deployed `hash` to, from its BridgeableContractDeployed event in
+// the receipt, or from the registry if the event isn't there.
+async fn deployed_address(
+    origin: &Deployment,
+    receipt: &TransactionReceipt,
+    hash: H256,
+) -> Result<Address> {
+    let factory = format!("{:?}", origin.factory.address());
+    let hash_hex = format!("{:?}", hash);
+    let event = receipt.logs.iter().filter_map(decode_log).find_map(|event| match event {
+        DecodedEvent::BridgeableContractDeployed(e)
+            if e.base.contract == factory && e.hash == hash_hex =>
+        {
+            Some(e.contract_address)
+        }
+        _ => None,
+    });
+    let address = match event {
+        Some(address) => address.parse().map_err(|_| {
+            Error::Decode(format!("Invalid address in BridgeableContractDeployed: {}", address))
+        })?,
+        None => origin.registry.method("getHolographedHashAddress", hash)?.call().await?,
+    };
+    if address == Address::zero() {
+        return Err(Error::NotFound(format!("No contract was deployed for {:?}", hash)));
+    }
+    Ok(address)
+}
+
+fn native_token(deployment: &Deployment) -> &'static str {
+    networks::get(&deployment.network.name).map_or("", |network| network.native_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deployment_config_hash() {
+        let deployment = DeploymentConfig {
+            contract_type: contract_type_key("HolographERC721"),
+            chain_type: 1,
+            salt: H256::from_low_u64_be(42),
+            byte_code: Bytes::from(hex::decode("6080604052").unwrap()),
+            init_code: Bytes::from(hex::decode("1234").unwrap()),
+        };
+        let signer: Address = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266".parse().unwrap();
+        // keccak256(abi.encodePacked(contractType, chainType, salt, keccak256(byteCode),
+        // keccak256(initCode), signer)), as HolographFactory hashes it
+        let expected: H256 =
+            "0xec3cd9b76b1e5bd65e5a42cd83d17fc311178b892cad3f7d37ab69c4b512e053".parse().unwrap();
+        assert_eq!(deployment.hash(signer), expected);
+    }
+}
@@ -0,0 +1,901 @@
+use crate::collections::{CollectionIndex, CollectionType};
+use crate::config::{Config, NetworkConfig};
+use crate::contracts::{call, get_abis};
+use crate::error::{Error, Result};
+use crate::events::DecodedEvent;
+use crate::inspect;
+use crate::merkle::{self, MerkleTree};
+use crate::networks;
+use crate::providers::{self, RpcProvider};
+use crate::transactions;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use colored::*;
+use ethers::abi::{self, Abi, Token, Tokenize};
+use ethers::contract::Contract;
+use ethers::prelude::*;
+use ethers::utils::{format_ether, format_units, keccak256, parse_units, ParseUnits};
+use serde::{Deserialize, Serialize};
+
+// Number of minters listed by `drop stats`
+const TOP_MINTERS: usize = 5;
+
+// Recipients are airdropped in batches of this size, one transaction per batch, and
+// a line of the recipients file can ask for at most this many tokens
+const AIRDROP_BATCH_SIZE: usize = 100;
+const MAX_AIRDROP_QUANTITY: usize = 1_000;
+
+// Purchases send this much more than quoted by default, in basis points, so a price
+// oracle update between the quote and the transaction doesn't make them revert
+const DEFAULT_MAX_SLIPPAGE_BPS: u64 = 100;
+
+// Flags that take a value, any other argument starting with `--` is a switch
+const VALUE_FLAGS: &[&str] = &[
+    "--network",
+    "--usd",
+    "--price",
+    "--max-per-address",
+    "--public-start",
+    "--public-end",
+    "--presale-start",
+    "--presale-end",
+    "--merkle-root",
+    "--presale-list",
+    "--from-batch",
+    "--max-slippage",
+];
+
+const USAGE: &str = "Usage: holograph-rs drop <command> [--network <name>] [--dry-run]
+  stats <address>
+  price <address> [--usd <amount>]
+  set-sale <address> [--price <usd>] [--max-per-address <n>] [--public-start <time>]
+           [--public-end <time>] [--presale-start <time>] [--presale-end <time>]
+           [--merkle-root <hash> | --presale-list <presale.csv>]
+  mint <address> <recipient> <quantity>
+  airdrop <address> <recipients.csv> [--from-batch <n>]
+  purchase <address> <quantity> [--max-slippage <percent>]
+  presale <address> <quantity> <presale.csv> [--max-slippage <percent>]
+  withdraw <address>
+  merkle-root <presale.csv>";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MinterSales {
+    pub mints: U256,
+    pub spent: U256,
+}
+
+// A `SalesConfigChanged`, with the public sale price it set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceChange {
+    pub block: u64,
+    pub changed_by: String,
+    pub public_sale_price: Option<U256>,
+}
+
+// Sales of a drop, built from the events of its source contract. Amounts are in the
+// native token of the network.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DropSales {
+    // Block and log index of the last event counted, so a block handled again after a
+    // restart isn't counted twice.
+    pub last_event: (u64, u32),
+    pub mints: U256,
+    pub revenue: U256,
+    pub mint_fees: U256,
+    pub withdrawn: U256,
+    pub minters: BTreeMap<String, MinterSales>,
+    pub price_changes: Vec<PriceChange>,
+    // Block the open edition was finalized in.
+    pub finalized_block: Option<u64>,
+}
+
+impl DropSales {
+    // Count a drop event. `public_sale_price` is the price read from the drop for a
+    // `SalesConfigChanged`. Returns false for events that were already counted or
+    // aren't drop events.
+    pub fn record(
+        &mut self,
+        block: u64,
+        event: &DecodedEvent,
+        public_sale_price: Option<U256>,
+    ) -> bool {
+        let position = match event {
+            DecodedEvent::Sale(e) => (block, e.base.log_index),
+            DecodedEvent::MintFeePayout(e) => (block, e.base.log_index),
+            DecodedEvent::FundsWithdrawn(e) => (block, e.base.log_index),
+            DecodedEvent::SalesConfigChanged(e) => (block, e.base.log_index),
+            DecodedEvent::OpenMintFinalized(e) => (block, e.base.log_index),
+            _ => return false,
+        };
+        if position <= self.last_event {
+            return false;
+        }
+        self.last_event = position;
+
+        match event {
+            DecodedEvent::Sale(sale) => {
+                let spent = sale.quantity * sale.price_per_token;
+                self.mints += sale.quantity;
+                self.revenue += spent;
+                let minter = self.minters.entry(sale.to.clone()).or_default();
+                minter.mints += sale.quantity;
+                minter.spent += spent;
+            }
+            DecodedEvent::MintFeePayout(payout) if payout.success => {
+                self.mint_fees += payout.mint_fee_amount;
+            }
+            DecodedEvent::FundsWithdrawn(withdrawal) => {
+                self.withdrawn += withdrawal.amount;
+            }
+            DecodedEvent::SalesConfigChanged(changed) => {
+                self.price_changes.push(PriceChange {
+                    block,
+                    changed_by: changed.changed_by.clone(),
+                    public_sale_price,
+                });
+            }
+            DecodedEvent::OpenMintFinalized(_) => self.finalized_block = Some(block),
+            _ => {}
+        }
+        true
+    }
+}
+
+// What a drop event did, for the monitor's log.
+pub fn describe(event: &DecodedEvent) -> Option<String> {
+    Some(match event {
+        DecodedEvent::Sale(sale) => format!(
+            "Sale of {} from token {} to {} at {} wei each",
+            sale.quantity, sale.first_purchased_token_id, sale.to, sale.price_per_token
+        ),
+        DecodedEvent::MintFeePayout(payout) => format!(
+            "Mint fee of {} wei to {} {}",
+            payout.mint_fee_amount,
+            payout.mint_fee_recipient,
+            if payout.success { "paid" } else { "failed" }
+        ),
+        DecodedEvent::FundsWithdrawn(withdrawal) => format!(
+            "{} withdrew {} wei to {}, with a fee of {} wei to {}",
+            withdrawal.withdrawn_by,
+            withdrawal.amount,
+            withdrawal.withdrawn_to,
+            withdrawal.fee_amount,
+            withdrawal.fee_recipient
+        ),
+        DecodedEvent::SalesConfigChanged(changed) => {
+            format!("Sales config changed by {}", changed.changed_by)
+        }
+        DecodedEvent::OpenMintFinalized(finalized) => format!(
+            "Open edition finalized by {} after {} mints",
+            finalized.sender, finalized.number_of_mints
+        ),
+        _ => return None,
+    })
+}
+
+// `saleDetails` of a HolographDropERC721.
+struct SaleDetails {
+    public_sale_active: bool,
+    presale_active: bool,
+    public_sale_price: U256,
+    public_sale_start: u64,
+    public_sale_end: u64,
+    presale_start: u64,
+    presale_end: u64,
+    presale_merkle_root: [u8; 32],
+    max_sale_purchase_per_address: U256,
+    total_minted: U256,
+    max_supply: U256,
+}
+
+type SaleDetailsTuple = (bool, bool, U256, u64, u64, u64, u64, [u8; 32], U256, U256, U256);
+
+// `salesConfig` of a HolographDropERC721, as taken by `setSaleConfiguration`.
+pub type SalesConfigTuple = (U256, u32, u64, u64, u64, u64, [u8; 32]);
+
+impl From<SaleDetailsTuple> for SaleDetails {
+    fn from(details: SaleDetailsTuple) -> Self {
+        SaleDetails {
+            public_sale_active: details.0,
+            presale_active: details.1,
+            public_sale_price: details.2,
+            public_sale_start: details.3,
+            public_sale_end: details.4,
+            presale_start: details.5,
+            presale_end: details.6,
+            presale_merkle_root: details.7,
+            max_sale_purchase_per_address: details.8,
+            total_minted: details.9,
+            max_supply: details.10,
+        }
+    }
+}
+
+// Sale prices are set in USD with 6 decimals and paid in the native token.
+pub fn format_usd(price: U256) -> String {
+    format!("${}", format_units(price, 6).unwrap_or_default())
+}
+
+fn format_time(timestamp: u64) -> String {
+    match chrono::DateTime::from_timestamp(timestamp as i64, 0) {
+        Some(time) if timestamp > 0 => time.to_rfc3339(),
+        _ => "-".to_string(),
+    }
+}
+
+// Value of a flag such as `--network <name>`.
+fn flag<'a>(args: &'a [String], name: &str) -> Result<Option<&'a str>> {
+    match args.iter().position(|arg| arg == name) {
+        Some(index) => match args.get(index + 1) {
+            Some(value) => Ok(Some(value.as_str())),
+            None => Err(Error::Config(format!("{} needs a value", name))),
+        },
+        None => Ok(None),
+    }
+}
+
+// The network a drop command runs on, `--network <name>` or the first configured one.
+fn network<'a>(config: &'a Config, args: &[String]) -> Result<&'a NetworkConfig> {
+    match flag(args, "--network")? {
+        Some(name) => config
+            .networks
+            .iter()
+            .find(|network| network.name == name)
+            .ok_or_else(|| Error::Config(format!("Network {} is not configured", name))),
+        None => config
+            .networks
+            .first()
+            .ok_or_else(|| Error::Config("No networks configured".to_string())),
+    }
+}
+
+// Arguments that aren't flags or flag values.
+fn positional(args: &[String]) -> Vec<&str> {
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            args.next();
+        } else if !arg.starts_with("--") {
+            positional.push(arg.as_str());
+        }
+    }
+    positional
+}
+
+// The positional argument at `index`, which the command can't do without.
+fn argument(args: &[String], index: usize) -> Result<&str> {
+    positional(args).get(index).copied().ok_or_else(|| Error::Config(USAGE.to_string()))
+}
+
+// Connect to a drop by the address of its collection or of its source contract.
+// Calls made to the holographer are forwarded to the drop.
+fn connect(
+    config: &Config,
+    network: &NetworkConfig,
+    address: &str,
+) -> Result<(Arc<RpcProvider>, Contract<RpcProvider>)> {
+    let address: Address =
+        address.parse().map_err(|_| Error::Config(format!("Invalid address: {}", address)))?;
+    let provider = Arc::new(providers::connect(
+        &network.name,
+        &network.rpc_urls,
+        inspect::log_channel(config),
+    )?);
+    let abi: Abi =
+        serde_json::from_str(get_abis(config.environment.as_str()).holograph_drop_erc721_abi)?;
+    let drop = Contract::new(address, abi, provider.clone());
+    Ok((provider, drop))
+}
+
+async fn stats(config: &Config, args: &[String]) -> Result<()> {
+    let network = network(config, args)?;
+    let address = argument(args, 1)?;
+    let (_, drop) = connect(config, network, address)?;
+    let native_token = networks::get(&network.name).map_or("", |n| n.native_token);
+
+    // Sales come from the index kept by the monitor
+    let index = CollectionIndex::load(&config.collections_file)?;
+    let key = address.to_lowercase();
+    let collection = index.get(&network.name, &key).or_else(|| {
+        index.find_by_source(&network.name, &key).and_then(|key| index.get(&network.name, &key))
+    });
+
+    let details: SaleDetails =
+        drop.method::<_, SaleDetailsTuple>("saleDetails", ())?.call().await?.into();
+    let native_price = call::<U256>(&drop, "getNativePrice").await;
+    // Open editions have a max supply of type(uint64).max
+    let remaining = if details.max_supply == U256::from(u64::MAX) {
+        "unlimited".to_string()
+    } else {
+        details.max_supply.saturating_sub(details.total_minted).to_string()
+    };
+
+    println!("{:<28} {} on {}", "drop", address, network.name);
+    println!("{:<28} {}", "minted", details.total_minted);
+    println!("{:<28} {}", "remaining", remaining);
+    println!(
+        "{:<28} {} ({})",
+        "public sale price",
+        format_usd(details.public_sale_price),
+        match native_price {
+            Ok(price) => format!("{} {}", format_ether(price), native_token),
+            Err(e) => format!("<{}>", e),
+        }
+    );
+    let active = |active: bool| if active { "active".green() } else { "inactive".yellow() };
+    println!(
+        "{:<28} {} {} to {}",
+        "public sale",
+        active(details.public_sale_active),
+        format_time(details.public_sale_start),
+        format_time(details.public_sale_end)
+    );
+    println!(
+        "{:<28} {} {} to {}",
+        "presale",
+        active(details.presale_active),
+        format_time(details.presale_start),
+        format_time(details.presale_end)
+    );
+    println!("{:<28} {:?}", "presale merkle root", H256::from(details.presale_merkle_root));
+    println!("{:<28} {}", "max per address", details.max_sale_purchase_per_address);
+
+    let Some(collection) = collection else {
+        println!("\n{}", "Not in the collection index, sales are recorded by the monitor".yellow());
+        return Ok(());
+    };
+    if collection.collection_type != CollectionType::Drop {
+        println!(
+            "\n{}",
+            format!("Indexed as {:?}, not a drop", collection.collection_type).yellow()
+        );
+        return Ok(());
+    }
+    let sales = collection.sales.unwrap_or_default();
+    println!();
+    println!("{:<28} {}", "indexed mints", sales.mints);
+    println!("{:<28} {} {}", "revenue", format_ether(sales.revenue), native_token);
+    println!("{:<28} {} {}", "mint fees", format_ether(sales.mint_fees), native_token);
+    println!("{:<28} {} {}", "withdrawn", format_ether(sales.withdrawn), native_token);
+    println!("{:<28} {}", "minters", sales.minters.len());
+    if let Some(block) = sales.finalized_block {
+        println!("{:<28} block {}", "open edition finalized", block);
+    }
+
+    let mut minters: Vec<_> = sales.minters.iter().collect();
+    minters.sort_by_key(|(_, minted)| std::cmp::Reverse(minted.mints));
+    for (minter, minted) in minters.into_iter().take(TOP_MINTERS) {
+        println!(
+            "  {:<42} {} minted, {} {}",
+            minter,
+            minted.mints,
+            format_ether(minted.spent),
+            native_token
+        );
+    }
+
+    if !sales.price_changes.is_empty() {
+        println!("\nprice changes");
+        for change in &sales.price_changes {
+            println!(
+                "  block {:<20} {} by {}",
+                change.block,
+                change.public_sale_price.map_or("-".to_string(), format_usd),
+                change.changed_by
+            );
+        }
+    }
+    Ok(())
+}
+
+fn parse_address(value: &str) -> Result<Address> {
+    value.trim().parse().map_err(|_| Error::Config(format!("Invalid address: {}", value)))
+}
+
+fn parse_u256(value: &str) -> Result<U256> {
+    U256::from_dec_str(value.trim())
+        .map_err(|_| Error::Config(format!("Invalid number: {}", value)))
+}
+
+// A USD amount such as `25.50`, in the 6 decimals sale prices are set in. Prices are
+// stored as uint104.
+fn parse_usd(value: &str) -> Result<U256> {
+    let invalid = || Error::Config(format!("Invalid USD amount: {}", value));
+    let amount = match parse_units(value.trim().trim_start_matches('$'), 6) {
+        Ok(ParseUnits::U256(amount)) => amount,
+        _ => return Err(invalid()),
+    };
+    if amount.bits() > 104 {
+        return Err(Error::Config(format!("USD amount {} is too large", value)));
+    }
+    Ok(amount)
+}
+
+// `--max-slippage` in percent, as basis points.
+fn max_slippage(args: &[String]) -> Result<u64> {
+    let Some(value) = flag(args, "--max-slippage")? else {
+        return Ok(DEFAULT_MAX_SLIPPAGE_BPS);
+    };
+    match parse_units(value.trim().trim_end_matches('%'), 2) {
+        Ok(ParseUnits::U256(bps)) if bps <= U256::from(10_000) => Ok(bps.as_u64()),
+        _ => Err(Error::Config(format!("Invalid maximum slippage: {}", value))),
+    }
+}
+
+// The value to send for a quoted price, with the slippage margin on top.
+fn with_slippage(quoted: U256, slippage_bps: u64) -> U256 {
+    quoted + quoted * slippage_bps / 10_000
+}
+
+// A sale time, as a unix timestamp or an RFC 3339 date.
+fn parse_time(value: &str) -> Result<u64> {
+    value.parse().or_else(|_| {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .map(|time| time.timestamp().max(0) as u64)
+            .map_err(|_| Error::Config(format!("Invalid time: {}", value)))
+    })
+}
+
+// Lines of a CSV file split into trimmed fields, without empty lines, `#` comments
+// and a header line.
+fn read_csv(path: &str) -> Result<Vec<Vec<String>>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::Config(format!("Failed to read {}: {}", path, e)))?;
+    let mut rows: Vec<Vec<String>> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.split(',').map(|field| field.trim().to_string()).collect())
+        .collect();
+    if rows.first().is_some_and(|row| parse_address(&row[0]).is_err()) {
+        rows.remove(0);
+    }
+    Ok(rows)
+}
+
+// An entry of a presale list: `address,max_quantity,price_usd`.
+struct PresaleEntry {
+    address: Address,
+    max_quantity: U256,
+    // In USD with 6 decimals, like the public sale price.
+    price_per_token: U256,
+}
+
+impl PresaleEntry {
+    // The leaf `purchasePresale` checks the proof against.
+    fn leaf(&self) -> H256 {
+        H256::from(keccak256(abi::encode(&[
+            Token::Address(self.address),
+            Token::Uint(self.max_quantity),
+            Token::Uint(self.price_per_token),
+        ])))
+    }
+}
+
+fn read_presale_list(path: &str) -> Result<Vec<PresaleEntry>> {
+    read_csv(path)?
+        .iter()
+        .map(|row| match row.as_slice() {
+            [address, max_quantity, price] => Ok(PresaleEntry {
+                address: parse_address(address)?,
+                max_quantity: parse_u256(max_quantity)?,
+                price_per_token: parse_usd(price)?,
+            }),
+            _ => Err(Error::Config(format!(
+                "Presale entries are address,max_quantity,price_usd, got: {}",
+                row.join(",")
+            ))),
+        })
+        .collect()
+}
+
+fn presale_tree(entries: &[PresaleEntry]) -> MerkleTree {
+    MerkleTree::new(entries.iter().map(PresaleEntry::leaf).collect())
+}
+
+// Convert a USD amount to the native token with the drop's `DropsPriceOracle`.
+async fn usd_to_wei(
+    config: &Config,
+    provider: &Arc<RpcProvider>,
+    drop: &Contract<RpcProvider>,
+    usd: U256,
+) -> Result<U256> {
+    let oracle: Address = call(drop, "dropsPriceOracle").await?;
+    let abi: Abi =
+        serde_json::from_str(get_abis(config.environment.as_str()).drops_price_oracle_abi)?;
+    let oracle = Contract::new(oracle, abi, provider.clone());
+    Ok(oracle.method::<_, U256>("convertUsdToWei", usd)?.call().await?)
+}
+
+// Admin functions revert for anyone else, check first to fail with a clear error. A
+// dry run can go without a wallet.
+async fn require_admin(config: &Config, drop: &Contract<RpcProvider>, dry_run: bool) -> Result<()> {
+    let wallet = match config.wallet.signer()? {
+        Some(wallet) => wallet,
+        None if dry_run => return Ok(()),
+        None => transactions::signer(config)?,
+    };
+    let is_admin: bool = drop.method("isAdmin", wallet.address())?.call().await?;
+    if !is_admin {
+        return Err(Error::Transaction(format!(
+            "{:?} is not an admin of drop {:?}",
+            wallet.address(),
+            drop.address()
+        )));
+    }
+    Ok(())
+}
+
+// Send a call to the drop from the configured wallet, or only describe it with
+// `--dry-run`.
+async fn send<T: Tokenize>(
+    config: &Config,
+    provider: &RpcProvider,
+    drop: &Contract<RpcProvider>,
+    method: &str,
+    args: T,
+    value: U256,
+    dry_run: bool,
+) -> Result<()> {
+    let calldata = drop
+        .method::<_, ()>(method, args)?
+        .calldata()
+        .ok_or_else(|| Error::Decode(format!("Failed to encode {}", method)))?;
+    let tx = TransactionRequest::new().to(drop.address()).data(calldata).value(value);
+    if dry_run {
+        println!("Dry run, {} not sent (value {} wei)", method, value);
+        return Ok(());
+    }
+    let wallet = transactions::signer(config)?;
+    let receipt = transactions::send(provider, &wallet, tx.into(), &config.gas).await?;
+    println!(
+        "{} {} in block {}",
+        method,
+        "succeeded".green(),
+        receipt.block_number.unwrap_or_default()
+    );
+    Ok(())
+}
+
+async fn price(config: &Config, args: &[String]) -> Result<()> {
+    let network = network(config, args)?;
+    let (provider, drop) = connect(config, network, argument(args, 1)?)?;
+    let native_token = networks::get(&network.name).map_or("", |n| n.native_token);
+
+    let sales_config: SalesConfigTuple = drop.method("salesConfig", ())?.call().await?;
+    let native_price: U256 = call(&drop, "getNativePrice").await?;
+    println!("{:<28} {}", "public sale price", format_usd(sales_config.0));
+    println!("{:<28} {} {}", "native price", format_ether(native_price), native_token);
+
+    let usd = match flag(args, "--usd")? {
+        Some(usd) => parse_usd(usd)?,
+        None => sales_config.0,
+    };
+    let oracle: Address = call(&drop, "dropsPriceOracle").await?;
+    let wei = usd_to_wei(config, &provider, &drop, usd).await?;
+    println!("{:<28} {:?}", "price oracle", oracle);
+    println!("{:<28} {} = {} {}", "quote", format_usd(usd), format_ether(wei), native_token);
+    Ok(())
+}
+
+// `set-sale`: change the flagged fields of the sales configuration, keeping the rest.
+async fn set_sale(config: &Config, args: &[String], dry_run: bool) -> Result<()> {
+    let network = network(config, args)?;
+    let (provider, drop) = connect(config, network, argument(args, 1)?)?;
+    let (
+        mut price,
+        mut max_per_address,
+        mut public_start,
+        mut public_end,
+        mut presale_start,
+        mut presale_end,
+        mut merkle_root,
+    ): SalesConfigTuple = drop.method("salesConfig", ())?.call().await?;
+
+    if let Some(value) = flag(args, "--price")? {
+        price = parse_usd(value)?;
+    }
+    if let Some(value) = flag(args, "--max-per-address")? {
+        max_per_address = value
+            .parse()
+            .map_err(|_| Error::Config(format!("Invalid maximum per address: {}", value)))?;
+    }
+    for (name, field) in [
+        ("--public-start", &mut public_start),
+        ("--public-end", &mut public_end),
+        ("--presale-start", &mut presale_start),
+        ("--presale-end", &mut presale_end),
+    ] {
+        if let Some(value) = flag(args, name)? {
+            *field = parse_time(value)?;
+        }
+    }
+    match (flag(args, "--merkle-root")?, flag(args, "--presale-list")?) {
+        (Some(_), Some(_)) => {
+            return Err(Error::Config(
+                "Use either --merkle-root or --presale-list, not both".to_string(),
+            ))
+        }
+        (Some(root), None) => {
+            let root: H256 = root
+                .parse()
+                .map_err(|_| Error::Config(format!("Invalid merkle root: {}", root)))?;
+            merkle_root = root.0;
+        }
+        (None, Some(path)) => merkle_root = presale_tree(&read_presale_list(path)?).root().0,
+        (None, None) => {}
+    }
+
+    println!("{:<28} {}", "public sale price", format_usd(price));
+    println!("{:<28} {}", "max per address", max_per_address);
+    println!("{:<28} {} to {}", "public sale", format_time(public_start), format_time(public_end));
+    println!("{:<28} {} to {}", "presale", format_time(presale_start), format_time(presale_end));
+    println!("{:<28} {:?}", "presale merkle root", H256::from(merkle_root));
+
+    require_admin(config, &drop, dry_run).await?;
+    let sales_config =
+        (price, max_per_address, public_start, public_end, presale_start, presale_end, merkle_root);
+    send(config, &provider, &drop, "setSaleConfiguration", sales_config, U256::zero(), dry_run)
+        .await
+}
+
+async fn mint(config: &Config, args: &[String], dry_run: bool) -> Result<()> {
+    let network = network(config, args)?;
+    let (provider, drop) = connect(config, network, argument(args, 1)?)?;
+    let recipient = parse_address(argument(args, 2)?)?;
+    let quantity = parse_u256(argument(args, 3)?)?;
+    require_admin(config, &drop, dry_run).await?;
+    println!("Minting {} to {:?}", quantity, recipient);
+    send(config, &provider, &drop, "adminMint", (recipient, quantity), U256::zero(), dry_run).await
+}
+
+// `airdrop`: mint one token to every recipient of a CSV, or `quantity` tokens when the
+// line is `address,quantity`. `--from-batch` resumes an airdrop that failed part way.
+async fn airdrop(config: &Config, args: &[String], dry_run: bool) -> Result<()> {
+    let network = network(config, args)?;
+    let (provider, drop) = connect(config, network, argument(args, 1)?)?;
+    let mut recipients = Vec::new();
+    for row in read_csv(argument(args, 2)?)? {
+        let address = parse_address(&row[0])?;
+        let quantity = match row.get(1) {
+            Some(quantity) => quantity
+                .parse()
+                .ok()
+                .filter(|quantity| (1..=MAX_AIRDROP_QUANTITY).contains(quantity))
+                .ok_or_else(|| {
+                    Error::Config(format!(
+                        "Invalid quantity for {}: {}, expected 1 to {}",
+                        row[0], quantity, MAX_AIRDROP_QUANTITY
+                    ))
+                })?,
+            None => 1,
+        };
+        recipients.extend(std::iter::repeat_n(address, quantity));
+    }
+    if recipients.is_empty() {
+        return Err(Error::Config("No airdrop recipients".to_string()));
+    }
+
+    let batches = recipients.chunks(AIRDROP_BATCH_SIZE).count();
+    let from_batch = match flag(args, "--from-batch")? {
+        Some(value) => {
+            value.parse().ok().filter(|batch| (1..=batches).contains(batch)).ok_or_else(|| {
+                Error::Config(format!("Invalid batch: {}, expected 1 to {}", value, batches))
+            })?
+        }
+        None => 1,
+    };
+    require_admin(config, &drop, dry_run).await?;
+
+    for (index, batch) in recipients.chunks(AIRDROP_BATCH_SIZE).enumerate().skip(from_batch - 1) {
+        let number = index + 1;
+        println!("Airdrop batch {}/{}: {} tokens", number, batches, batch.len());
+        let sent = send(
+            config,
+            &provider,
+            &drop,
+            "adminMintAirdrop",
+            batch.to_vec(),
+            U256::zero(),
+            dry_run,
+        )
+        .await;
+        if let Err(e) = sent {
+            eprintln!(
+                "{}",
+                format!(
+                    "Airdrop batch {}/{} failed, batches before it were minted. Resume with --from-batch {}",
+                    number, batches, number
+                )
+                .red()
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+async fn purchase(config: &Config, args: &[String], dry_run: bool) -> Result<()> {
+    let network = network(config, args)?;
+    let (provider, drop) = connect(config, network, argument(args, 1)?)?;
+    let quantity = parse_u256(argument(args, 2)?)?;
+    let slippage = max_slippage(args)?;
+    let native_token = networks::get(&network.name).map_or("", |n| n.native_token);
+
+    // The drop converts its USD price with the oracle, which is what this quotes
+    let native_price: U256 = call(&drop, "getNativePrice").await?;
+    let quoted = native_price * quantity;
+    let value = with_slippage(quoted, slippage);
+    println!("Purchasing {} quoted at {} {}", quantity, format_ether(quoted), native_token);
+    println!(
+        "Sending {} {} (max slippage {}%)",
+        format_ether(value),
+        native_token,
+        format_units(slippage, 2).unwrap_or_default()
+    );
+    send(config, &provider, &drop, "purchase", quantity, value, dry_run).await
+}
+
+// `presale`: buy from the presale as the configured wallet, proving its entry in the
+// presale list the merkle root was built from.
+async fn presale(config: &Config, args: &[String], dry_run: bool) -> Result<()> {
+    let network = network(config, args)?;
+    let (provider, drop) = connect(config, network, argument(args, 1)?)?;
+    let quantity = parse_u256(argument(args, 2)?)?;
+    let entries = read_presale_list(argument(args, 3)?)?;
+    let slippage = max_slippage(args)?;
+    let buyer = transactions::signer(config)?.address();
+    let native_token = networks::get(&network.name).map_or("", |n| n.native_token);
+
+    let index = entries
+        .iter()
+        .position(|entry| entry.address == buyer)
+        .ok_or_else(|| Error::Config(format!("{:?} is not on the presale list", buyer)))?;
+    let entry = &entries[index];
+    if quantity > entry.max_quantity {
+        return Err(Error::Config(format!(
+            "{:?} can buy at most {} in the presale",
+            buyer, entry.max_quantity
+        )));
+    }
+
+    let tree = presale_tree(&entries);
+    let proof = tree.proof(index);
+    let sales_config: SalesConfigTuple = drop.method("salesConfig", ())?.call().await?;
+    let root = H256::from(sales_config.6);
+    if !merkle::verify(&proof, root, entry.leaf()) {
+        return Err(Error::Config(format!(
+            "The presale list doesn't match the drop's merkle root {:?} (list root {:?})",
+            root,
+            tree.root()
+        )));
+    }
+
+    let quoted = usd_to_wei(config, &provider, &drop, entry.price_per_token).await? * quantity;
+    let value = with_slippage(quoted, slippage);
+    println!(
+        "Presale purchase of {} at {} each quoted at {} {}",
+        quantity,
+        format_usd(entry.price_per_token),
+        format_ether(quoted),
+        native_token
+    );
+    println!(
+        "Sending {} {} (max slippage {}%)",
+        format_ether(value),
+        native_token,
+        format_units(slippage, 2).unwrap_or_default()
+    );
+    let proof: Vec<[u8; 32]> = proof.iter().map(|hash| hash.0).collect();
+    let args = (quantity, entry.max_quantity, entry.price_per_token, proof);
+    send(config, &provider, &drop, "purchasePresale", args, value, dry_run).await
+}
+
+async fn withdraw(config: &Config, args: &[String], dry_run: bool) -> Result<()> {
+    let network = network(config, args)?;
+    let (provider, drop) = connect(config, network, argument(args, 1)?)?;
+    let native_token = networks::get(&network.name).map_or("", |n| n.native_token);
+    let balance = provider.get_balance(drop.address(), None).await?;
+    println!("Withdrawing {} {}", format_ether(balance), native_token);
+    send(config, &provider, &drop, "withdraw", (), U256::zero(), dry_run).await
+}
+
+// `merkle-root`: the presale merkle root of a presale list, for `set-sale`.
+fn merkle_root(args: &[String]) -> Result<()> {
+    let entries = read_presale_list(argument(args, 1)?)?;
+    println!("{:<28} {}", "entries", entries.len());
+    println!("{:<28} {:?}", "presale merkle root", presale_tree(&entries).root());
+    Ok(())
+}
+
+// `drop <command>`: commands for HolographDropERC721 collections.
+pub async fn run(config: &Config, args: &[String]) -> Result<()> {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    match positional(args).first().copied() {
+        Some("stats") => stats(config, args).await,
+        Some("price") => price(config, args).await,
+        Some("set-sale") => set_sale(config, args, dry_run).await,
+        Some("mint") => mint(config, args, dry_run).await,
+        Some("airdrop") => airdrop(config, args, dry_run).await,
+        Some("purchase") => purchase(config, args, dry_run).await,
+        Some("presale") => presale(config, args, dry_run).await,
+        Some("withdraw") => withdraw(config, args, dry_run).await,
+        Some("merkle-root") => merkle_root(args),
+        Some(command) => {
+            Err(Error::Config(format!("Unknown drop command: {}\n{}", command, USAGE)))
+        }
+        None => Err(Error::Config(USAGE.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{BaseEvent, EventType, SaleEvent};
+
+    fn sale(log_index: u32, to: &str, quantity: u64) -> DecodedEvent {
+        DecodedEvent::Sale(SaleEvent {
+            base: BaseEvent {
+                event_type: EventType::SaleDrop,
+                contract: "0x0000000000000000000000000000000000000001".to_string(),
+                log_index,
+            },
+            to: to.to_string(),
+            quantity: quantity.into(),
+            price_per_token: U256::exp10(15),
+            first_purchased_token_id: U256::one(),
+        })
+    }
+
+    #[test]
+    fn drop_sales_count_each_event_once() {
+        let mut sales = DropSales::default();
+        assert!(sales.record(10, &sale(3, "0xa", 2), None));
+        assert!(sales.record(10, &sale(5, "0xb", 1), None));
+        // The same events again, e.g. a block handled again after a restart
+        assert!(!sales.record(10, &sale(3, "0xa", 2), None));
+        assert!(!sales.record(10, &sale(5, "0xb", 1), None));
+        // Anything before the last counted event is a replay too
+        assert!(!sales.record(9, &sale(7, "0xa", 1), None));
+        assert!(sales.record(11, &sale(0, "0xa", 1), None));
+
+        assert_eq!(sales.last_event, (11, 0));
+        assert_eq!(sales.mints, U256::from(4));
+        assert_eq!(sales.revenue, U256::exp10(15) * 4);
+        assert_eq!(sales.minters["0xa"].mints, U256::from(3));
+        assert_eq!(sales.minters["0xb"].mints, U256::one());
+    }
+
+    // keccak256(abi.encode(address, uint256, uint256)) computed separately
+    #[test]
+    fn presale_leaf() {
+        let entry = PresaleEntry {
+            address: "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266".parse().unwrap(),
+            max_quantity: 3.into(),
+            price_per_token: parse_usd("25.50").unwrap(),
+        };
+        let expected: H256 =
+            "0x588d7d93ae2b647f079adf6506e8659045373190b93b28f67610243e6f0ef978".parse().unwrap();
+        assert_eq!(entry.leaf(), expected);
+    }
+
+    #[test]
+    fn usd_amounts() {
+        assert_eq!(parse_usd("$25.50").unwrap(), U256::from(25_500_000));
+        assert_eq!(parse_usd("0").unwrap(), U256::zero());
+        assert!(parse_usd("-1").is_err());
+        assert!(parse_usd("ten").is_err());
+        // uint104 holds a little over 2e31, i.e. 2e25 USD with 6 decimals
+        assert!(parse_usd("20000000000000000000000000").is_ok());
+        assert!(parse_usd("30000000000000000000000000").is_err());
+    }
+
+    #[test]
+    fn slippage_margin() {
+        assert_eq!(with_slippage(U256::from(10_000), 0), U256::from(10_000));
+        assert_eq!(with_slippage(U256::from(10_000), DEFAULT_MAX_SLIPPAGE_BPS), U256::from(10_100));
+    }
+}
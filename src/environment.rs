@@ -6,3 +6,27 @@ pub enum Environment {
     Testnet,
     Mainnet,
 }
+
+impl Environment {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "localhost" => Some(Environment::Localhost),
+            "experimental" => Some(Environment::Experimental),
+            "develop" => Some(Environment::Develop),
+            "testnet" => Some(Environment::Testnet),
+            "mainnet" => Some(Environment::Mainnet),
+            _ => None,
+        }
+    }
+
+    // Name used in configuration and for the ABI directories.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Localhost => "localhost",
+            Environment::Experimental => "experimental",
+            Environment::Develop => "develop",
+            Environment::Testnet => "testnet",
+            Environment::Mainnet => "mainnet",
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::providers::RpcProvider;
+use crate::retry::RetryError;
+
+use std::fmt;
+
+use ethers::contract::{AbiError, ContractError};
+use ethers::prelude::*;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Crate wide error type, with a variant per subsystem so callers (and alerting on
+// the logs) can tell what went wrong without parsing messages.
+#[derive(Debug)]
+pub enum Error {
+    // Missing or invalid configuration, e.g. environment variables or networks.
+    Config(String),
+    // RPC errors returned by a provider.
+    Provider(ProviderError),
+    // A contract call that reverted, or whose arguments or result could not be encoded.
+    // Calls that failed to reach a node are `Provider` errors, those can be retried.
+    Contract(ContractError<RpcProvider>),
+    // Something we expected to be deployed or loaded is not there.
+    NotFound(String),
+    // ABI or JSON that could not be encoded or decoded.
+    Decode(String),
+    // Reading or writing local state such as checkpoints failed.
+    Storage(String),
+    // A transaction we wanted to send was refused, could not be sent or reverted.
+    Transaction(String),
+    // The deployments on the configured networks don't match.
+    Inconsistent(String),
+    // A call that kept failing after being retried.
+    Retry(Box<RetryError<Error>>),
+}
+
+impl Error {
+    // Short, stable name of the variant, used as the error type in logs.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Config(_) => "config",
+            Error::Provider(_) => "provider",
+            Error::Contract(_) => "contract",
+            Error::NotFound(_) => "not_found",
+            Error::Decode(_) => "decode",
+            Error::Storage(_) => "storage",
+            Error::Transaction(_) => "transaction",
+            Error::Inconsistent(_) => "inconsistent",
+            Error::Retry(e) => e.source.kind(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Config(msg) => write!(f, "Configuration error: {}", msg),
+            Error::Provider(e) => write!(f, "Provider error: {}", e),
+            Error::Contract(e) => write!(f, "Contract error: {}", e),
+            Error::NotFound(msg) => write!(f, "Not found: {}", msg),
+            Error::Decode(msg) => write!(f, "Decoding error: {}", msg),
+            Error::Storage(msg) => write!(f, "Storage error: {}", msg),
+            Error::Transaction(msg) => write!(f, "Transaction error: {}", msg),
+            Error::Inconsistent(msg) => write!(f, "Inconsistent deployment: {}", msg),
+            Error::Retry(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Provider(e) => Some(e),
+            Error::Contract(e) => Some(e),
+            Error::Retry(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProviderError> for Error {
+    fn from(e: ProviderError) -> Self {
+        Error::Provider(e)
+    }
+}
+
+impl From<ContractError<RpcProvider>> for Error {
+    fn from(e: ContractError<RpcProvider>) -> Self {
+        match e {
+            ContractError::MiddlewareError { e } | ContractError::ProviderError { e } => {
+                Error::Provider(e)
+            }
+            e => Error::Contract(e),
+        }
+    }
+}
+
+impl From<AbiError> for Error {
+    fn from(e: AbiError) -> Self {
+        Error::Decode(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Decode(e.to_string())
+    }
+}
+
+impl<E: Into<Error>> From<RetryError<E>> for Error {
+    fn from(e: RetryError<E>) -> Self {
+        Error::Retry(Box::new(RetryError {
+            attempts: e.attempts,
+            class: e.class,
+            source: e.source.into(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contract_errors_tell_reverts_from_provider_failures() {
+        let failed = ContractError::<RpcProvider>::ProviderError {
+            e: ProviderError::CustomError("connection refused".to_string()),
+        };
+        assert_eq!(Error::from(failed).kind(), "provider");
+
+        let reverted =
+            ContractError::<RpcProvider>::Revert(Bytes::from_static(b"\x08\xc3\x79\xa0"));
+        assert_eq!(Error::from(reverted).kind(), "contract");
+    }
+}
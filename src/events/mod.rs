@@ -4,9 +4,10 @@ pub type BloomFilter = Vec<u8>; // Placeholder type. This should be replaced wit
 pub type BloomFilterMap = HashMap<EventType, BloomFilter>;
 
 use ethers::abi::Abi; // This is the closest thing to the `Interface` in ethers.js
+use ethers::abi::{ParamType, Token};
 use ethers::types::H256;
-use ethers::types::{Log, U256};
-use ethers::utils::id;
+use ethers::types::{Address, Log, U256};
+use ethers::utils::keccak256;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum EventType {
@@ -29,78 +30,415 @@ pub enum EventType {
     V1PacketLZ,
     TestLzEvent,
     HolographableContractEvent,
+    SaleDrop,
+    MintFeePayoutDrop,
+    FundsWithdrawnDrop,
+    SalesConfigChangedDrop,
+    OpenMintFinalizedDrop,
 }
 
+impl EventType {
+    // Solidity signature of the event, used to derive its topic hash
+    pub fn signature(&self) -> Option<&'static str> {
+        match self {
+            EventType::TransferERC20
+            | EventType::HolographableTransferERC20
+            | EventType::TransferERC721
+            | EventType::HolographableTransferERC721 => Some("Transfer(address,address,uint256)"),
+            EventType::TransferSingleERC1155 | EventType::HolographableTransferSingleERC1155 => {
+                Some("TransferSingle(address,address,address,uint256,uint256)")
+            }
+            EventType::TransferBatchERC1155 | EventType::HolographableTransferBatchERC1155 => {
+                Some("TransferBatch(address,address,address,uint256[],uint256[])")
+            }
+            EventType::BridgeableContractDeployed => {
+                Some("BridgeableContractDeployed(address,bytes32)")
+            }
+            EventType::CrossChainMessageSent => Some("CrossChainMessageSent(bytes32)"),
+            EventType::AvailableOperatorJob => Some("AvailableOperatorJob(bytes32,bytes)"),
+            EventType::FinishedOperatorJob => Some("FinishedOperatorJob(bytes32,address)"),
+            EventType::FailedOperatorJob => Some("FailedOperatorJob(bytes32)"),
+            EventType::PacketLZ => Some("Packet(uint16,bytes)"),
+            EventType::V1PacketLZ => Some("Packet(bytes)"),
+            EventType::TestLzEvent => Some("LzEvent(uint16,bytes,bytes)"),
+            EventType::HolographableContractEvent => {
+                Some("HolographableContractEvent(address,bytes)")
+            }
+            EventType::SaleDrop => Some("Sale(address,uint256,uint256,uint256)"),
+            EventType::MintFeePayoutDrop => Some("MintFeePayout(uint256,address,bool)"),
+            EventType::FundsWithdrawnDrop => {
+                Some("FundsWithdrawn(address,address,uint256,address,uint256)")
+            }
+            EventType::SalesConfigChangedDrop => Some("SalesConfigChanged(address)"),
+            EventType::OpenMintFinalizedDrop => Some("OpenMintFinalized(address,uint256)"),
+            EventType::UNKNOWN | EventType::TBD => None,
+        }
+    }
+
+    // Topic hash (topic0) of the event
+    pub fn sig_hash(&self) -> Option<H256> {
+        self.signature().map(|signature| H256::from(keccak256(signature)))
+    }
+}
+
+// Events are decoded in full, even where nothing reads a field yet
+#[allow(dead_code)]
 pub struct BaseEvent {
-    event_type: EventType,
-    contract: String,
-    log_index: u32, // Equivalent to `number` in TypeScript for non-negative integers
+    pub event_type: EventType,
+    pub contract: String,
+    pub log_index: u32, // Equivalent to `number` in TypeScript for non-negative integers
 }
 
+#[allow(dead_code)]
 pub struct HolographableContractEvent {
-    base: BaseEvent,
-    contract_address: String,
-    payload: String,
+    pub base: BaseEvent,
+    pub contract_address: String,
+    pub payload: String,
 }
 
+#[allow(dead_code)]
 pub struct TransferERC20Event {
-    base: BaseEvent,
-    from: String,
-    to: String,
-    value: U256, // Equivalent to `BigNumber` in TypeScript
+    pub base: BaseEvent,
+    pub from: String,
+    pub to: String,
+    pub value: U256, // Equivalent to `BigNumber` in TypeScript
 }
 
+#[allow(dead_code)]
 pub struct TransferERC721Event {
-    base: BaseEvent,
-    from: String,
-    to: String,
-    token_id: U256,
+    pub base: BaseEvent,
+    pub from: String,
+    pub to: String,
+    pub token_id: U256,
 }
 
+#[allow(dead_code)]
 pub struct TransferSingleERC1155Event {
-    base: BaseEvent,
-    operator: String,
-    from: String,
-    to: String,
-    token_id: U256,
-    value: U256,
+    pub base: BaseEvent,
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    pub token_id: U256,
+    pub value: U256,
 }
 
+#[allow(dead_code)]
 pub struct TransferBatchERC1155Event {
-    base: BaseEvent,
-    operator: String,
-    from: String,
-    to: String,
-    token_ids: Vec<U256>, // Equivalent to `BigNumber[]` in TypeScript
-    values: Vec<U256>,
+    pub base: BaseEvent,
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    pub token_ids: Vec<U256>, // Equivalent to `BigNumber[]` in TypeScript
+    pub values: Vec<U256>,
 }
 
 pub struct BridgeableContractDeployedEvent {
-    base: BaseEvent,
-    contract_address: String,
-    hash: String,
+    pub base: BaseEvent,
+    pub contract_address: String,
+    pub hash: String,
 }
 
+#[allow(dead_code)]
 pub struct CrossChainMessageSentEvent {
-    base: BaseEvent,
-    message_hash: String,
+    pub base: BaseEvent,
+    pub message_hash: String,
 }
 
+#[allow(dead_code)]
 pub struct AvailableOperatorJobEvent {
-    base: BaseEvent,
-    job_hash: String,
-    payload: String,
+    pub base: BaseEvent,
+    pub job_hash: String,
+    pub payload: String,
 }
 
 pub struct FinishedOperatorJobEvent {
-    base: BaseEvent,
-    job_hash: String,
-    operator: String,
+    pub base: BaseEvent,
+    pub job_hash: String,
+    pub operator: String,
 }
 
 pub struct FailedOperatorJobEvent {
-    base: BaseEvent,
-    job_hash: String,
+    pub base: BaseEvent,
+    pub job_hash: String,
+}
+
+// Events of a HolographDropERC721. They are emitted by the drop itself, the source
+// contract behind the collection's holographer.
+pub struct SaleEvent {
+    pub base: BaseEvent,
+    pub to: String,
+    pub quantity: U256,
+    // In the native token, converted from the USD sale price by the price oracle.
+    pub price_per_token: U256,
+    pub first_purchased_token_id: U256,
+}
+
+pub struct MintFeePayoutEvent {
+    pub base: BaseEvent,
+    pub mint_fee_amount: U256,
+    pub mint_fee_recipient: String,
+    pub success: bool,
+}
+
+pub struct FundsWithdrawnEvent {
+    pub base: BaseEvent,
+    pub withdrawn_by: String,
+    pub withdrawn_to: String,
+    pub amount: U256,
+    pub fee_recipient: String,
+    pub fee_amount: U256,
+}
+
+pub struct SalesConfigChangedEvent {
+    pub base: BaseEvent,
+    pub changed_by: String,
+}
+
+pub struct OpenMintFinalizedEvent {
+    pub base: BaseEvent,
+    pub sender: String,
+    pub number_of_mints: U256,
+}
+
+// A log decoded into one of the events we know about
+pub enum DecodedEvent {
+    HolographableContract(HolographableContractEvent),
+    TransferERC20(TransferERC20Event),
+    TransferERC721(TransferERC721Event),
+    TransferSingleERC1155(TransferSingleERC1155Event),
+    TransferBatchERC1155(TransferBatchERC1155Event),
+    BridgeableContractDeployed(BridgeableContractDeployedEvent),
+    CrossChainMessageSent(CrossChainMessageSentEvent),
+    AvailableOperatorJob(AvailableOperatorJobEvent),
+    FinishedOperatorJob(FinishedOperatorJobEvent),
+    FailedOperatorJob(FailedOperatorJobEvent),
+    Sale(SaleEvent),
+    MintFeePayout(MintFeePayoutEvent),
+    FundsWithdrawn(FundsWithdrawnEvent),
+    SalesConfigChanged(SalesConfigChangedEvent),
+    OpenMintFinalized(OpenMintFinalizedEvent),
+}
+
+impl DecodedEvent {
+    // The log the event was decoded from.
+    pub fn base(&self) -> &BaseEvent {
+        match self {
+            DecodedEvent::HolographableContract(event) => &event.base,
+            DecodedEvent::TransferERC20(event) => &event.base,
+            DecodedEvent::TransferERC721(event) => &event.base,
+            DecodedEvent::TransferSingleERC1155(event) => &event.base,
+            DecodedEvent::TransferBatchERC1155(event) => &event.base,
+            DecodedEvent::BridgeableContractDeployed(event) => &event.base,
+            DecodedEvent::CrossChainMessageSent(event) => &event.base,
+            DecodedEvent::AvailableOperatorJob(event) => &event.base,
+            DecodedEvent::FinishedOperatorJob(event) => &event.base,
+            DecodedEvent::FailedOperatorJob(event) => &event.base,
+            DecodedEvent::Sale(event) => &event.base,
+            DecodedEvent::MintFeePayout(event) => &event.base,
+            DecodedEvent::FundsWithdrawn(event) => &event.base,
+            DecodedEvent::SalesConfigChanged(event) => &event.base,
+            DecodedEvent::OpenMintFinalized(event) => &event.base,
+        }
+    }
+
+    // Whether the event is emitted by a drop, see `drops`.
+    pub fn is_drop_event(&self) -> bool {
+        matches!(
+            self,
+            DecodedEvent::Sale(_)
+                | DecodedEvent::MintFeePayout(_)
+                | DecodedEvent::FundsWithdrawn(_)
+                | DecodedEvent::SalesConfigChanged(_)
+                | DecodedEvent::OpenMintFinalized(_)
+        )
+    }
+
+    // Hash of the operator job the event belongs to, if it is an operator event.
+    pub fn job_hash(&self) -> Option<&str> {
+        match self {
+            DecodedEvent::AvailableOperatorJob(event) => Some(&event.job_hash),
+            DecodedEvent::FinishedOperatorJob(event) => Some(&event.job_hash),
+            DecodedEvent::FailedOperatorJob(event) => Some(&event.job_hash),
+            _ => None,
+        }
+    }
+}
+
+fn topic_address(log: &Log, index: usize) -> Option<String> {
+    log.topics.get(index).map(|topic| format!("{:?}", Address::from(*topic)))
+}
+
+fn topic_u256(log: &Log, index: usize) -> Option<U256> {
+    log.topics.get(index).map(|topic| U256::from_big_endian(topic.as_bytes()))
+}
+
+fn topic_hash(log: &Log, index: usize) -> Option<String> {
+    log.topics.get(index).map(|topic| format!("{:?}", topic))
+}
+
+fn decode_data(log: &Log, types: &[ParamType]) -> Option<Vec<Token>> {
+    ethers::abi::decode(types, &log.data).ok()
+}
+
+fn token_hex(token: &Token) -> Option<String> {
+    match token {
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => {
+            Some(format!("0x{}", ethers::utils::hex::encode(bytes)))
+        }
+        Token::Address(address) => Some(format!("{:?}", address)),
+        _ => None,
+    }
+}
+
+fn token_u256s(token: &Token) -> Option<Vec<U256>> {
+    match token {
+        Token::Array(values) => values.iter().map(|value| value.clone().into_uint()).collect(),
+        _ => None,
+    }
+}
+
+// Decode a log into a known event by its topic hash. Transfer events share a topic
+// between ERC20 and ERC721, and are told apart by whether the token id is indexed.
+pub fn decode_log(log: &Log) -> Option<DecodedEvent> {
+    let topic = *log.topics.first()?;
+    let base = |event_type: EventType| BaseEvent {
+        event_type,
+        contract: format!("{:?}", log.address),
+        log_index: log.log_index.map(|index| index.as_u32()).unwrap_or_default(),
+    };
+    let is = |event_type: EventType| event_type.sig_hash() == Some(topic);
+
+    if is(EventType::TransferERC721) && log.topics.len() == 4 {
+        return Some(DecodedEvent::TransferERC721(TransferERC721Event {
+            base: base(EventType::TransferERC721),
+            from: topic_address(log, 1)?,
+            to: topic_address(log, 2)?,
+            token_id: topic_u256(log, 3)?,
+        }));
+    }
+    if is(EventType::TransferERC20) && log.topics.len() == 3 {
+        let data = decode_data(log, &[ParamType::Uint(256)])?;
+        return Some(DecodedEvent::TransferERC20(TransferERC20Event {
+            base: base(EventType::TransferERC20),
+            from: topic_address(log, 1)?,
+            to: topic_address(log, 2)?,
+            value: data[0].clone().into_uint()?,
+        }));
+    }
+    if is(EventType::TransferSingleERC1155) {
+        let data = decode_data(log, &[ParamType::Uint(256), ParamType::Uint(256)])?;
+        return Some(DecodedEvent::TransferSingleERC1155(TransferSingleERC1155Event {
+            base: base(EventType::TransferSingleERC1155),
+            operator: topic_address(log, 1)?,
+            from: topic_address(log, 2)?,
+            to: topic_address(log, 3)?,
+            token_id: data[0].clone().into_uint()?,
+            value: data[1].clone().into_uint()?,
+        }));
+    }
+    if is(EventType::TransferBatchERC1155) {
+        let uint_array = ParamType::Array(Box::new(ParamType::Uint(256)));
+        let data = decode_data(log, &[uint_array.clone(), uint_array])?;
+        return Some(DecodedEvent::TransferBatchERC1155(TransferBatchERC1155Event {
+            base: base(EventType::TransferBatchERC1155),
+            operator: topic_address(log, 1)?,
+            from: topic_address(log, 2)?,
+            to: topic_address(log, 3)?,
+            token_ids: token_u256s(&data[0])?,
+            values: token_u256s(&data[1])?,
+        }));
+    }
+    if is(EventType::BridgeableContractDeployed) {
+        return Some(DecodedEvent::BridgeableContractDeployed(BridgeableContractDeployedEvent {
+            base: base(EventType::BridgeableContractDeployed),
+            contract_address: topic_address(log, 1)?,
+            hash: topic_hash(log, 2)?,
+        }));
+    }
+    if is(EventType::HolographableContractEvent) {
+        let data = decode_data(log, &[ParamType::Bytes])?;
+        return Some(DecodedEvent::HolographableContract(HolographableContractEvent {
+            base: base(EventType::HolographableContractEvent),
+            contract_address: topic_address(log, 1)?,
+            payload: token_hex(&data[0])?,
+        }));
+    }
+    if is(EventType::CrossChainMessageSent) {
+        let data = decode_data(log, &[ParamType::FixedBytes(32)])?;
+        return Some(DecodedEvent::CrossChainMessageSent(CrossChainMessageSentEvent {
+            base: base(EventType::CrossChainMessageSent),
+            message_hash: token_hex(&data[0])?,
+        }));
+    }
+    if is(EventType::AvailableOperatorJob) {
+        let data = decode_data(log, &[ParamType::FixedBytes(32), ParamType::Bytes])?;
+        return Some(DecodedEvent::AvailableOperatorJob(AvailableOperatorJobEvent {
+            base: base(EventType::AvailableOperatorJob),
+            job_hash: token_hex(&data[0])?,
+            payload: token_hex(&data[1])?,
+        }));
+    }
+    if is(EventType::FinishedOperatorJob) {
+        let data = decode_data(log, &[ParamType::FixedBytes(32), ParamType::Address])?;
+        return Some(DecodedEvent::FinishedOperatorJob(FinishedOperatorJobEvent {
+            base: base(EventType::FinishedOperatorJob),
+            job_hash: token_hex(&data[0])?,
+            operator: token_hex(&data[1])?,
+        }));
+    }
+    if is(EventType::FailedOperatorJob) {
+        let data = decode_data(log, &[ParamType::FixedBytes(32)])?;
+        return Some(DecodedEvent::FailedOperatorJob(FailedOperatorJobEvent {
+            base: base(EventType::FailedOperatorJob),
+            job_hash: token_hex(&data[0])?,
+        }));
+    }
+    if is(EventType::SaleDrop) && log.topics.len() == 4 {
+        let data = decode_data(log, &[ParamType::Uint(256)])?;
+        return Some(DecodedEvent::Sale(SaleEvent {
+            base: base(EventType::SaleDrop),
+            to: topic_address(log, 1)?,
+            quantity: topic_u256(log, 2)?,
+            price_per_token: topic_u256(log, 3)?,
+            first_purchased_token_id: data[0].clone().into_uint()?,
+        }));
+    }
+    if is(EventType::MintFeePayoutDrop) {
+        let data = decode_data(log, &[ParamType::Uint(256), ParamType::Address, ParamType::Bool])?;
+        return Some(DecodedEvent::MintFeePayout(MintFeePayoutEvent {
+            base: base(EventType::MintFeePayoutDrop),
+            mint_fee_amount: data[0].clone().into_uint()?,
+            mint_fee_recipient: token_hex(&data[1])?,
+            success: data[2].clone().into_bool()?,
+        }));
+    }
+    if is(EventType::FundsWithdrawnDrop) {
+        let data =
+            decode_data(log, &[ParamType::Uint(256), ParamType::Address, ParamType::Uint(256)])?;
+        return Some(DecodedEvent::FundsWithdrawn(FundsWithdrawnEvent {
+            base: base(EventType::FundsWithdrawnDrop),
+            withdrawn_by: topic_address(log, 1)?,
+            withdrawn_to: topic_address(log, 2)?,
+            amount: data[0].clone().into_uint()?,
+            fee_recipient: token_hex(&data[1])?,
+            fee_amount: data[2].clone().into_uint()?,
+        }));
+    }
+    if is(EventType::SalesConfigChangedDrop) {
+        return Some(DecodedEvent::SalesConfigChanged(SalesConfigChangedEvent {
+            base: base(EventType::SalesConfigChangedDrop),
+            changed_by: topic_address(log, 1)?,
+        }));
+    }
+    if is(EventType::OpenMintFinalizedDrop) {
+        let data = decode_data(log, &[ParamType::Uint(256)])?;
+        return Some(DecodedEvent::OpenMintFinalized(OpenMintFinalizedEvent {
+            base: base(EventType::OpenMintFinalizedDrop),
+            sender: topic_address(log, 1)?,
+            number_of_mints: data[0].clone().into_uint()?,
+        }));
+    }
+    None
 }
 
 struct Event {
@@ -0,0 +1,290 @@
+use crate::types::{InterestingTransaction, TransactionType};
+
+use std::collections::HashMap;
+
+use ethers::abi::ethereum_types::BloomInput;
+use ethers::types::{Bloom, Log, Transaction, H256};
+
+pub enum FilterType {
+    To,
+    From,
+    FunctionSig,
+    EventHash,
+}
+
+pub enum MatchField {
+    SimpleMatch(String),
+    // For network dependant filters this maps a network name to the value to match,
+    // otherwise any of the values in the map is a match.
+    ComplexMatch(HashMap<String, String>),
+}
+
+pub struct TransactionFilter {
+    pub bloom_id: String,
+    pub filter_type: FilterType,
+    pub match_field: MatchField,
+    pub network_dependant: bool,
+}
+
+impl TransactionFilter {
+    // Values this filter matches on the given network, lowercased for comparison.
+    fn values(&self, network: &str) -> Vec<String> {
+        match &self.match_field {
+            MatchField::SimpleMatch(value) => vec![value.to_lowercase()],
+            MatchField::ComplexMatch(values) if self.network_dependant => {
+                values.get(network).map(|value| vec![value.to_lowercase()]).unwrap_or_default()
+            }
+            MatchField::ComplexMatch(values) => {
+                values.values().map(|value| value.to_lowercase()).collect()
+            }
+        }
+    }
+
+    pub fn matches_transaction(&self, network: &str, transaction: &Transaction) -> bool {
+        let field = match self.filter_type {
+            FilterType::To => match transaction.to {
+                Some(to) => format!("{:?}", to),
+                None => return false,
+            },
+            FilterType::From => format!("{:?}", transaction.from),
+            FilterType::FunctionSig => {
+                if transaction.input.len() < 4 {
+                    return false;
+                }
+                format!("0x{}", ethers::utils::hex::encode(&transaction.input[..4]))
+            }
+            FilterType::EventHash => return false,
+        };
+        self.values(network).contains(&field)
+    }
+
+    pub fn matches_log(&self, network: &str, log: &Log) -> bool {
+        match (&self.filter_type, log.topics.first()) {
+            (FilterType::EventHash, Some(topic)) => {
+                self.values(network).contains(&format!("{:?}", topic))
+            }
+            _ => false,
+        }
+    }
+
+    // Event topics this filter is looking for, used to check a block's logs bloom.
+    fn topics(&self, network: &str) -> Vec<H256> {
+        match self.filter_type {
+            FilterType::EventHash => {
+                self.values(network).iter().filter_map(|value| value.parse().ok()).collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+// Check whether a block's logs bloom may contain any of the events we filter for.
+// A bloom can give false positives but never false negatives, so logs only need
+// to be fetched when this returns true.
+pub fn check_bloom_logs(bloom: &Bloom, network: &str, filters: &[TransactionFilter]) -> bool {
+    filters
+        .iter()
+        .flat_map(|filter| filter.topics(network))
+        .any(|topic| bloom.contains_input(BloomInput::Raw(topic.as_bytes())))
+}
+
+// Match block transactions and logs against the filter list. A transaction is
+// reported once for the first transaction filter it matches, and once more for
+// every log of it that matches an event filter.
+pub fn filter_transactions(
+    network: &str,
+    filters: &[TransactionFilter],
+    transactions: &[Transaction],
+    logs: &[Log],
+) -> Vec<InterestingTransaction> {
+    let mut interesting_transactions = Vec::new();
+
+    for transaction in transactions {
+        if let Some(filter) =
+            filters.iter().find(|filter| filter.matches_transaction(network, transaction))
+        {
+            interesting_transactions.push(InterestingTransaction {
+                bloom_id: filter.bloom_id.clone(),
+                transaction: transaction.clone(),
+                receipt: None,
+                log: None,
+                all_logs: None,
+                events: Vec::new(),
+                transaction_type: TransactionType::Unknown,
+            });
+        }
+    }
+
+    for log in logs {
+        let filter = match filters.iter().find(|filter| filter.matches_log(network, log)) {
+            Some(filter) => filter,
+            None => continue,
+        };
+        let transaction =
+            transactions.iter().find(|transaction| Some(transaction.hash) == log.transaction_hash);
+        if let Some(transaction) = transaction {
+            interesting_transactions.push(InterestingTransaction {
+                bloom_id: filter.bloom_id.clone(),
+                transaction: transaction.clone(),
+                receipt: None,
+                log: Some(log.clone()),
+                all_logs: None,
+                events: Vec::new(),
+                transaction_type: TransactionType::Unknown,
+            });
+        }
+    }
+
+    interesting_transactions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ethers::types::{Address, Bytes};
+
+    fn address(n: u64) -> Address {
+        Address::from_low_u64_be(n)
+    }
+
+    fn transaction(hash: u64, from: Address, to: Option<Address>, input: &[u8]) -> Transaction {
+        Transaction {
+            hash: H256::from_low_u64_be(hash),
+            from,
+            to,
+            input: Bytes::from(input.to_vec()),
+            ..Default::default()
+        }
+    }
+
+    fn log(transaction: u64, topic: H256) -> Log {
+        Log {
+            topics: vec![topic],
+            transaction_hash: Some(H256::from_low_u64_be(transaction)),
+            ..Default::default()
+        }
+    }
+
+    fn filter(bloom_id: &str, filter_type: FilterType, value: String) -> TransactionFilter {
+        TransactionFilter {
+            bloom_id: bloom_id.to_string(),
+            filter_type,
+            match_field: MatchField::SimpleMatch(value),
+            network_dependant: false,
+        }
+    }
+
+    fn matched(interesting: &[InterestingTransaction]) -> Vec<(u64, &str, bool)> {
+        interesting
+            .iter()
+            .map(|interesting| {
+                (
+                    interesting.transaction.hash.to_low_u64_be(),
+                    interesting.bloom_id.as_str(),
+                    interesting.log.is_some(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_matching_transaction_filter_wins() {
+        let filters = [
+            filter("to", FilterType::To, format!("{:?}", address(0xaa))),
+            filter("selector", FilterType::FunctionSig, "0x12345678".to_string()),
+            // Mixed case values still match
+            filter("from", FilterType::From, format!("{:?}", address(0xcc)).to_uppercase()),
+        ];
+        let transactions = [
+            transaction(1, address(0xcc), Some(address(0xaa)), &[0x12, 0x34, 0x56, 0x78]),
+            transaction(2, address(0xcc), Some(address(0xbb)), &[0x12, 0x34, 0x56, 0x78, 0]),
+            transaction(3, address(0xcc), None, &[0x12, 0x34]),
+            transaction(4, address(0xdd), Some(address(0xbb)), &[]),
+        ];
+
+        let interesting = filter_transactions("optimism", &filters, &transactions, &[]);
+        assert_eq!(
+            matched(&interesting),
+            [(1, "to", false), (2, "selector", false), (3, "from", false)]
+        );
+
+        // Reordering the filters changes which one is reported
+        let [to, selector, from] = filters;
+        let filters = [from, to, selector];
+        let interesting = filter_transactions("optimism", &filters, &transactions, &[]);
+        assert_eq!(
+            matched(&interesting),
+            [(1, "from", false), (2, "from", false), (3, "from", false)]
+        );
+    }
+
+    #[test]
+    fn network_dependant_filters_only_match_their_network() {
+        let values = HashMap::from([
+            ("optimism".to_string(), format!("{:?}", address(0xaa))),
+            ("base".to_string(), format!("{:?}", address(0xbb))),
+        ]);
+        let per_network = TransactionFilter {
+            bloom_id: "operator".to_string(),
+            filter_type: FilterType::To,
+            match_field: MatchField::ComplexMatch(values.clone()),
+            network_dependant: true,
+        };
+        let any_network = TransactionFilter {
+            bloom_id: "operator".to_string(),
+            filter_type: FilterType::To,
+            match_field: MatchField::ComplexMatch(values),
+            network_dependant: false,
+        };
+        let to_base = transaction(1, address(1), Some(address(0xbb)), &[]);
+
+        assert!(!per_network.matches_transaction("optimism", &to_base));
+        assert!(per_network.matches_transaction("base", &to_base));
+        assert!(!per_network.matches_transaction("zora", &to_base));
+        assert!(any_network.matches_transaction("optimism", &to_base));
+    }
+
+    #[test]
+    fn logs_are_reported_once_per_matching_log() {
+        let topic = H256::from_low_u64_be(0xe0);
+        let filters = [
+            filter("from", FilterType::From, format!("{:?}", address(0xcc))),
+            filter("event", FilterType::EventHash, format!("{:?}", topic)),
+        ];
+        let transactions = [
+            transaction(1, address(0xcc), Some(address(0xaa)), &[]),
+            transaction(2, address(0xdd), Some(address(0xaa)), &[]),
+        ];
+        let logs = [
+            log(1, topic),
+            log(2, topic),
+            log(2, topic),
+            // Other events, and logs of transactions outside the block, are ignored
+            log(2, H256::from_low_u64_be(0xe1)),
+            log(3, topic),
+        ];
+
+        let interesting = filter_transactions("optimism", &filters, &transactions, &logs);
+        assert_eq!(
+            matched(&interesting),
+            [(1, "from", false), (1, "event", true), (2, "event", true), (2, "event", true)]
+        );
+    }
+
+    #[test]
+    fn bloom_is_checked_for_event_filters_only() {
+        let topic = H256::from_low_u64_be(0xe0);
+        let mut bloom = Bloom::default();
+        bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+
+        let event = [filter("event", FilterType::EventHash, format!("{:?}", topic))];
+        let other_event =
+            [filter("event", FilterType::EventHash, format!("{:?}", H256::from_low_u64_be(1)))];
+        let to = [filter("to", FilterType::To, format!("{:?}", topic))];
+
+        assert!(check_bloom_logs(&bloom, "optimism", &event));
+        assert!(!check_bloom_logs(&bloom, "optimism", &other_event));
+        assert!(!check_bloom_logs(&bloom, "optimism", &to));
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// How long a block stream may go without a new block before the monitor stops
+// reporting ready, unless configured otherwise.
+pub const DEFAULT_STALL_THRESHOLD: Duration = Duration::from_secs(120);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ProviderStatus {
+    NotConfigured,
+    Connected,
+    Disconnected,
+}
+
+impl ProviderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderStatus::NotConfigured => "not_configured",
+            ProviderStatus::Connected => "connected",
+            ProviderStatus::Disconnected => "disconnected",
+        }
+    }
+}
+
+struct NetworkHealth {
+    status: ProviderStatus,
+    // When the block stream last moved the block height forward.
+    last_advanced: Option<Instant>,
+}
+
+// State behind the readiness check, updated by the subscriptions and by
+// `initialize_ethers`.
+pub struct Health {
+    contracts_loaded: AtomicBool,
+    networks: Mutex<HashMap<String, NetworkHealth>>,
+    stall_threshold: Duration,
+}
+
+impl Health {
+    pub fn new(networks: &[String], stall_threshold: Duration) -> Self {
+        let networks = networks
+            .iter()
+            .map(|network| {
+                let health =
+                    NetworkHealth { status: ProviderStatus::NotConfigured, last_advanced: None };
+                (network.clone(), health)
+            })
+            .collect();
+        Health {
+            contracts_loaded: AtomicBool::new(false),
+            networks: Mutex::new(networks),
+            stall_threshold,
+        }
+    }
+
+    fn networks(&self) -> std::sync::MutexGuard<'_, HashMap<String, NetworkHealth>> {
+        self.networks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_contracts_loaded(&self) {
+        self.contracts_loaded.store(true, Ordering::SeqCst);
+    }
+
+    // Set the provider status of a network, returning the previous status.
+    pub fn set_status(&self, network: &str, status: ProviderStatus) -> ProviderStatus {
+        let mut networks = self.networks();
+        let health = networks.entry(network.to_string()).or_insert(NetworkHealth {
+            status: ProviderStatus::NotConfigured,
+            last_advanced: None,
+        });
+        std::mem::replace(&mut health.status, status)
+    }
+
+    pub fn block_advanced(&self, network: &str) {
+        if let Some(health) = self.networks().get_mut(network) {
+            health.last_advanced = Some(Instant::now());
+        }
+    }
+
+    // Check whether the monitor is ready, returning a JSON report of every network.
+    // `heights` is the current block height map of the monitor.
+    pub fn readiness(&self, heights: &HashMap<String, u64>) -> (bool, serde_json::Value) {
+        let contracts_loaded = self.contracts_loaded.load(Ordering::SeqCst);
+        let mut ready = contracts_loaded;
+
+        let mut report = serde_json::Map::new();
+        for (network, health) in self.networks().iter() {
+            let since_block = health.last_advanced.map(|at| at.elapsed());
+            let stalled = since_block.is_none_or(|since| since > self.stall_threshold);
+            let network_ready = health.status == ProviderStatus::Connected && !stalled;
+            ready &= network_ready;
+
+            report.insert(
+                network.clone(),
+                serde_json::json!({
+                    "ready": network_ready,
+                    "status": health.status.as_str(),
+                    "block": heights.get(network),
+                    "seconds_since_block": since_block.map(|since| since.as_secs()),
+                }),
+            );
+        }
+
+        let body = serde_json::json!({
+            "ready": ready,
+            "contracts_loaded": contracts_loaded,
+            "networks": report,
+        });
+        (ready, body)
+    }
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health::new(&[], DEFAULT_STALL_THRESHOLD)
+    }
+}
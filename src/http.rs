@@ -0,0 +1,127 @@
+use crate::error::{Error, Result};
+use crate::health::Health;
+use crate::metrics::Metrics;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+// Requests larger than this are not something our endpoints expect.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: String) -> Self {
+        Response { status: 200, content_type, body }
+    }
+
+    pub fn json(status: u16, body: &serde_json::Value) -> Self {
+        Response { status, content_type: "application/json", body: body.to_string() }
+    }
+
+    pub fn not_found() -> Self {
+        Response { status: 404, content_type: "text/plain", body: "Not Found\n".to_string() }
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+// State behind the operational endpoints. It is cloned from the monitor before
+// `initialize_ethers` runs, so readiness can be reported while contracts load.
+pub struct Endpoints {
+    pub metrics: Arc<Metrics>,
+    pub health: Arc<Health>,
+    pub current_block_height: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl Endpoints {
+    async fn route(&self, path: &str) -> Response {
+        match path {
+            "/metrics" => Response::ok("text/plain; version=0.0.4", self.metrics.render()),
+            // The process is up and answering requests
+            "/healthz" => Response::ok("text/plain", "ok\n".to_string()),
+            "/readyz" => {
+                let heights = self.current_block_height.lock().await.clone();
+                let (ready, report) = self.health.readiness(&heights);
+                Response::json(if ready { 200 } else { 503 }, &report)
+            }
+            _ => Response::not_found(),
+        }
+    }
+}
+
+// Serve the operational endpoints on `addr`. This is deliberately tiny: only GET
+// requests are answered, and the request path is all that is looked at.
+pub async fn serve(addr: &str, endpoints: Arc<Endpoints>) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| Error::Config(format!("Failed to bind HTTP server to {}: {}", addr, e)))?;
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(_) => continue,
+        };
+        let endpoints = endpoints.clone();
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, &endpoints).await;
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, endpoints: &Endpoints) -> std::io::Result<()> {
+    // Read until the end of the headers, we don't accept request bodies
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+    // Ignore query strings, none of the endpoints take parameters
+    let path = path.split('?').next().unwrap_or_default();
+
+    let response = if method == "GET" {
+        endpoints.route(path).await
+    } else {
+        Response {
+            status: 405,
+            content_type: "text/plain",
+            body: "Method Not Allowed\n".to_string(),
+        }
+    };
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,360 @@
+use crate::config::{Config, NetworkConfig};
+use crate::contracts::{call, get_abis, holograph_addresses, ContractAbis};
+use crate::error::{Error, Result};
+use crate::logging::{LogRecord, Logger};
+use crate::networks;
+use crate::providers::{self, FailoverClient, RpcProvider};
+use crate::LogMessage;
+
+use std::fmt;
+use std::sync::Arc;
+
+use colored::*;
+use ethers::abi::Abi;
+use ethers::contract::Contract;
+use ethers::types::{Address, U256};
+use futures::future::join_all;
+use tokio::sync::mpsc;
+
+// One value read from a deployment, e.g. the implementation behind a proxy.
+pub struct Entry {
+    key: String,
+    value: String,
+    // Values that are expected to be different on every network, like the LayerZero
+    // endpoint. These are listed but never reported as a difference.
+    per_network: bool,
+}
+
+// Everything read from the deployment on one network, in the order it was read.
+#[derive(Default)]
+pub struct Report {
+    entries: Vec<Entry>,
+}
+
+impl Report {
+    pub fn push<T: fmt::Debug>(&mut self, key: impl Into<String>, value: &Result<T>) {
+        self.push_value(key, value, false);
+    }
+
+    pub fn push_value<T: fmt::Debug>(
+        &mut self,
+        key: impl Into<String>,
+        value: &Result<T>,
+        per_network: bool,
+    ) {
+        let value = match value {
+            Ok(value) => format!("{:?}", value),
+            Err(e) => format!("<{}>", e),
+        };
+        self.entries.push(Entry { key: key.into(), value, per_network });
+    }
+
+    fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+// A contract sitting behind a Holograph proxy.
+struct Proxied<'a> {
+    name: &'a str,
+    // Getter on `Holograph` for the proxy, and on the proxy for its implementation.
+    getter: &'a str,
+    proxy_abi: &'a str,
+    abi: &'a str,
+    // Getters for the other contracts this one points at.
+    pointers: &'a [&'a str],
+}
+
+fn proxied(abis: &ContractAbis) -> Vec<Proxied<'_>> {
+    vec![
+        Proxied {
+            name: "bridge",
+            getter: "getBridge",
+            proxy_abi: abis.holograph_bridge_proxy_abi,
+            abi: abis.holograph_bridge_abi,
+            pointers: &["getHolograph", "getFactory", "getOperator", "getRegistry"],
+        },
+        Proxied {
+            name: "factory",
+            getter: "getFactory",
+            proxy_abi: abis.holograph_factory_proxy_abi,
+            abi: abis.holograph_factory_abi,
+            pointers: &["getHolograph", "getRegistry"],
+        },
+        Proxied {
+            name: "operator",
+            getter: "getOperator",
+            proxy_abi: abis.holograph_operator_proxy_abi,
+            abi: abis.holograph_operator_abi,
+            pointers: &[
+                "getHolograph",
+                "getBridge",
+                "getInterfaces",
+                "getRegistry",
+                "getUtilityToken",
+                "getMessagingModule",
+            ],
+        },
+        Proxied {
+            name: "registry",
+            getter: "getRegistry",
+            proxy_abi: abis.holograph_registry_proxy_abi,
+            abi: abis.holograph_registry_abi,
+            pointers: &["getHolograph", "getUtilityToken"],
+        },
+        Proxied {
+            name: "treasury",
+            getter: "getTreasury",
+            proxy_abi: abis.holograph_treasury_proxy_abi,
+            abi: abis.holograph_treasury_abi,
+            pointers: &["getHolograph", "getBridge", "getOperator", "getRegistry"],
+        },
+    ]
+}
+
+// Read everything we know about the Holograph deployment on a network. Failed calls
+// are recorded in the report, so one broken contract doesn't hide the rest.
+async fn inspect_network(
+    network: &NetworkConfig,
+    networks: &[NetworkConfig],
+    holograph_address: Address,
+    abis: &ContractAbis,
+    tx_logs: mpsc::Sender<LogMessage>,
+) -> Result<Report> {
+    let provider = Arc::new(providers::connect(&network.name, &network.rpc_urls, tx_logs)?);
+    let contract = |abi: &str, address: Address| -> Result<Contract<RpcProvider>> {
+        let abi: Abi = serde_json::from_str(abi)?;
+        Ok(Contract::new(address, abi, provider.clone()))
+    };
+
+    let mut report = Report::default();
+    let client: &FailoverClient = (*provider).as_ref();
+    if let Some(expected) = networks::get(&network.name) {
+        let mismatches = client.verify_chain_id(expected.chain_id).await;
+        let chain: Result<u64> = if mismatches.is_empty() {
+            Ok(expected.chain_id)
+        } else {
+            Err(Error::Config(mismatches.join("; ")))
+        };
+        report.push_value("chain id", &chain, true);
+    }
+
+    let holograph = contract(abis.holograph_abi, holograph_address)?;
+    report.push("holograph", &Ok(holograph_address));
+    report.push("holograph.admin", &call::<Address>(&holograph, "getAdmin").await);
+
+    let mut operator_address = None;
+    for proxied in proxied(abis) {
+        let proxy_address = call::<Address>(&holograph, proxied.getter).await;
+        report.push(proxied.name, &proxy_address);
+        let Ok(proxy_address) = proxy_address else {
+            continue;
+        };
+        if proxied.name == "operator" {
+            operator_address = Some(proxy_address);
+        }
+
+        let proxy = contract(proxied.proxy_abi, proxy_address)?;
+        report.push(
+            format!("{}.implementation", proxied.name),
+            &call::<Address>(&proxy, proxied.getter).await,
+        );
+        report.push(format!("{}.admin", proxied.name), &call::<Address>(&proxy, "getAdmin").await);
+
+        // The proxy forwards everything else to the implementation
+        let implementation = contract(proxied.abi, proxy_address)?;
+        for pointer in proxied.pointers {
+            report.push(
+                format!("{}.{}", proxied.name, pointer),
+                &call::<Address>(&implementation, pointer).await,
+            );
+        }
+    }
+
+    let interfaces = call::<Address>(&holograph, "getInterfaces").await;
+    report.push("interfaces", &interfaces);
+    if let Ok(interfaces) = interfaces {
+        let interfaces = contract(abis.holograph_interfaces_abi, interfaces)?;
+        report.push("interfaces.admin", &call::<Address>(&interfaces, "getAdmin").await);
+    }
+    report.push("utilityToken", &call::<Address>(&holograph, "getUtilityToken").await);
+
+    // The messaging module is not proxied, it is read from the operator
+    let Some(operator_address) = operator_address else {
+        return Ok(report);
+    };
+    let operator = contract(abis.holograph_operator_abi, operator_address)?;
+    let Ok(module_address) = call::<Address>(&operator, "getMessagingModule").await else {
+        return Ok(report);
+    };
+    let module = contract(abis.layer_zero_module_abi, module_address)?;
+    report.push("messagingModule.admin", &call::<Address>(&module, "getAdmin").await);
+    for pointer in ["getBridge", "getInterfaces", "getOperator"] {
+        report
+            .push(format!("messagingModule.{}", pointer), &call::<Address>(&module, pointer).await);
+    }
+    report.push_value(
+        "messagingModule.getLZEndpoint",
+        &call::<Address>(&module, "getLZEndpoint").await,
+        true,
+    );
+
+    // Gas parameters are kept per destination, by Holograph chain ID
+    for destination in networks {
+        let Some(holograph_id) = networks::get(&destination.name).map(|n| n.holograph_id) else {
+            continue;
+        };
+        let parameters = async {
+            let (
+                msg_base_gas,
+                msg_gas_per_byte,
+                job_base_gas,
+                job_gas_per_byte,
+                min_gas_price,
+                max_gas_limit,
+            ): (U256, U256, U256, U256, U256, U256) =
+                module.method("getGasParameters", holograph_id)?.call().await?;
+            Ok(GasParameters {
+                msg_base_gas,
+                msg_gas_per_byte,
+                job_base_gas,
+                job_gas_per_byte,
+                min_gas_price,
+                max_gas_limit,
+            })
+        }
+        .await;
+        report.push(format!("gasParameters.{}", destination.name), &parameters);
+    }
+
+    Ok(report)
+}
+
+// `GasParameters` of the LayerZero module.
+struct GasParameters {
+    msg_base_gas: U256,
+    msg_gas_per_byte: U256,
+    job_base_gas: U256,
+    job_gas_per_byte: U256,
+    min_gas_price: U256,
+    max_gas_limit: U256,
+}
+
+impl fmt::Debug for GasParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "msgBaseGas={} msgGasPerByte={} jobBaseGas={} jobGasPerByte={} minGasPrice={} maxGasLimit={}",
+            self.msg_base_gas,
+            self.msg_gas_per_byte,
+            self.job_base_gas,
+            self.job_gas_per_byte,
+            self.min_gas_price,
+            self.max_gas_limit
+        )
+    }
+}
+
+// Print every value once, or once per network when the networks disagree. Networks
+// without a value are shown but not counted as a difference. Returns the number of
+// differences.
+pub fn print_reports(reports: &[(&str, Report)], only_differences: bool) -> usize {
+    let mut keys: Vec<&str> = Vec::new();
+    for (_, report) in reports {
+        for entry in &report.entries {
+            if !keys.contains(&entry.key.as_str()) {
+                keys.push(&entry.key);
+            }
+        }
+    }
+
+    let mut differences = 0;
+    for key in keys {
+        let values: Vec<(&str, Option<&Entry>)> =
+            reports.iter().map(|(network, report)| (*network, report.get(key))).collect();
+        let per_network = values.iter().any(|(_, entry)| entry.is_some_and(|e| e.per_network));
+        let mut present = values.iter().filter_map(|(_, entry)| entry.map(|e| &e.value));
+        let first = present.next();
+        let same = present.all(|value| Some(value) == first);
+
+        if same && !per_network {
+            if !only_differences {
+                println!("{:<40} {}", key, first.map_or("-", |value| value.as_str()));
+            }
+            continue;
+        }
+        if same || per_network {
+            if only_differences {
+                continue;
+            }
+            println!("{}", key);
+        } else {
+            differences += 1;
+            println!("{}", format!("{} differs", key).red());
+        }
+        for (network, entry) in values {
+            println!("  {:<38} {}", network, entry.map_or("-", |e| e.value.as_str()));
+        }
+    }
+
+    if reports.len() > 1 {
+        let summary = format!("{} difference(s) across {} networks", differences, reports.len());
+        if differences == 0 {
+            println!("\n{}", summary.green());
+        } else {
+            println!("\n{}", summary.red());
+        }
+    }
+    differences
+}
+
+pub fn holograph_address(config: &Config) -> Result<Address> {
+    holograph_addresses().get(&config.environment).copied().ok_or_else(|| {
+        Error::NotFound(format!("Holograph address not found for {:?}", config.environment))
+    })
+}
+
+// Commands print their results, but RPC failover messages still go through the logger.
+pub fn log_channel(config: &Config) -> mpsc::Sender<LogMessage> {
+    let (tx_logs, mut rx_logs) = mpsc::channel::<LogMessage>(32);
+    let logger = Logger::new(config.log_format, config.log_level);
+    let environment = format!("{:?}", config.environment);
+    tokio::spawn(async move {
+        while let Some(log_msg) = rx_logs.recv().await {
+            logger.log(&LogRecord {
+                level: log_msg.level,
+                environment: &environment,
+                context: &log_msg.context,
+                msg: &log_msg.msg,
+            });
+        }
+    });
+    tx_logs
+}
+
+// `inspect`: resolve the Holograph deployment on every configured network and show
+// where the networks disagree.
+pub async fn run(config: &Config) -> Result<()> {
+    let abis = get_abis(config.environment.as_str());
+    let holograph_address = holograph_address(config)?;
+    let tx_logs = log_channel(config);
+
+    let inspections = config.networks.iter().map(|network| {
+        inspect_network(network, &config.networks, holograph_address, &abis, tx_logs.clone())
+    });
+    let results = join_all(inspections).await;
+
+    let mut reports = Vec::new();
+    for (network, result) in config.networks.iter().zip(results) {
+        match result {
+            Ok(report) => reports.push((network.name.as_str(), report)),
+            Err(e) => eprintln!("Failed to inspect {} [{}]: {}", network.name, e.kind(), e),
+        }
+    }
+    if reports.is_empty() {
+        return Err(Error::NotFound("No network could be inspected".to_string()));
+    }
+
+    print_reports(&reports, false);
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use colored::*;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LogFormat {
+    // Colored, human readable lines. The default, meant for terminals.
+    Text,
+    // One JSON object per line, meant for log aggregators.
+    Json,
+}
+
+// Where a log line comes from. Everything is optional so messages that aren't
+// about a network, like startup and shutdown, can be logged too.
+#[derive(Debug, Default, Clone)]
+pub struct LogContext {
+    pub network: Option<String>,
+    pub block: Option<u64>,
+    pub job_hash: Option<String>,
+    pub tag_id: Option<String>,
+}
+
+impl LogContext {
+    pub fn network(network: &str) -> Self {
+        LogContext { network: Some(network.to_string()), ..Default::default() }
+    }
+
+    pub fn block(mut self, block: u64) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn job_hash(mut self, job_hash: Option<String>) -> Self {
+        self.job_hash = job_hash;
+        self
+    }
+
+    pub fn tag(mut self, tag_id: Option<&str>) -> Self {
+        self.tag_id = tag_id.map(str::to_string);
+        self
+    }
+}
+
+// A single log line with its context.
+pub struct LogRecord<'a> {
+    pub level: LogLevel,
+    pub environment: &'a str,
+    pub context: &'a LogContext,
+    pub msg: &'a str,
+}
+
+pub struct Logger {
+    format: LogFormat,
+    min_level: LogLevel,
+}
+
+impl Logger {
+    pub fn new(format: LogFormat, min_level: LogLevel) -> Self {
+        Logger { format, min_level }
+    }
+
+    // Write a record to stdout, or to stderr for warnings and errors.
+    pub fn log(&self, record: &LogRecord) {
+        if record.level < self.min_level {
+            return;
+        }
+
+        let line = match self.format {
+            LogFormat::Text => format_text(record),
+            LogFormat::Json => format_json(record),
+        };
+
+        if record.level >= LogLevel::Warn {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Logger { format: LogFormat::Text, min_level: LogLevel::Info }
+    }
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+fn format_text(record: &LogRecord) -> String {
+    let timestamp = chrono::Utc::now().format("%+").to_string();
+
+    // Errors get a red timestamp and an [ERROR] tag to stand out
+    let (timestamp_color, network_color) = match record.level {
+        LogLevel::Error => ("red", "blue"),
+        LogLevel::Warn => ("yellow", "red"),
+        _ => ("green", "red"),
+    };
+
+    let context = record.context;
+    let network =
+        context.network.as_deref().map(capitalize).unwrap_or_else(|| "Holograph".to_string());
+
+    let mut tag_string = match record.level {
+        LogLevel::Error => "[ERROR] ".to_string(),
+        LogLevel::Warn => "[WARN] ".to_string(),
+        _ => "".to_string(),
+    };
+    if let Some(block) = context.block {
+        tag_string += &format!("[block {}] ", block);
+    }
+    if let Some(job_hash) = &context.job_hash {
+        tag_string += &format!("[job {}] ", job_hash);
+    }
+    if let Some(tag) = &context.tag_id {
+        tag_string += &format!("[{}] ", tag);
+    }
+
+    format!(
+        "[{}] [{}] [{}] {}{}",
+        timestamp.color(timestamp_color),
+        network.color(network_color),
+        record.environment.color("cyan"),
+        tag_string,
+        record.msg.trim_start() // Remove leading whitespaces from the message
+    )
+}
+
+fn format_json(record: &LogRecord) -> String {
+    serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "level": record.level.as_str(),
+        "network": record.context.network,
+        "environment": record.environment,
+        "tag": record.context.tag_id,
+        "block": record.context.block,
+        "job_hash": record.context.job_hash,
+        "message": record.msg.trim_start(),
+    })
+    .to_string()
+}
@@ -1,21 +1,62 @@
+mod checkpoint;
+mod collections;
+mod config;
+mod confirmations;
+mod consistency;
 mod contracts;
+mod deploy;
+mod drops;
 mod environment;
+mod error;
 mod events;
+mod filters;
+mod health;
+mod http;
+mod inspect;
+mod logging;
+mod merkle;
+mod metrics;
+mod networks;
+mod pipeline;
+mod providers;
+mod receipts;
+mod reorg;
+mod retry;
+mod shutdown;
+mod subscription;
+#[cfg(test)]
+mod test_rpc;
+mod transactions;
 mod types;
 
+use checkpoint::Checkpoints;
+use collections::{Collection, CollectionIndex, CollectionType};
+use config::Config;
+use confirmations::{BlockFinality, ConfirmationPolicy};
 use contracts::{get_abis, holograph_addresses, ContractAbis};
 use environment::Environment;
+use error::{Error, Result};
+use events::{decode_log, DecodedEvent};
 use events::{BloomFilter, BloomFilterMap, BloomType, EventType};
-use types::InterestingTransaction;
-
-use std::collections::HashMap;
+use filters::{check_bloom_logs, filter_transactions, FilterType, MatchField, TransactionFilter};
+use health::Health;
+use http::Endpoints;
+use logging::{LogContext, LogLevel, LogRecord, Logger};
+use metrics::{JobOutcome, Metrics};
+use networks::ChainIdType;
+use providers::{RpcProvider, ENDPOINT_CHECK_INTERVAL};
+use receipts::{enrich_transactions, ReceiptCache};
+use retry::{RetryError, RetryPolicy};
+use subscription::BlockSubscription;
+use types::{InterestingTransaction, LogsParams};
+
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
-use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 
-use colored::*;
 use ethers::abi::Abi;
 use ethers::contract::Contract;
 use ethers::prelude::*;
@@ -30,31 +71,27 @@ const ONE: u8 = 1;
 const TWO: u8 = 2;
 const TEN: u8 = 10;
 
-enum OperatorMode {
-    Listen,
-    Manual,
-    Auto,
-}
+// Largest block range requested in a single eth_getLogs call. Most providers reject
+// or truncate larger ranges.
+const MAX_LOGS_BLOCK_RANGE: u64 = 1_000;
+const DEFAULT_LOGS_ATTEMPTS: u64 = 10;
+const DEFAULT_LOGS_INTERVAL: u64 = 5_000;
 
-enum ProviderStatus {
-    NotConfigured,
-    Connected,
-    Disconnected,
-}
+// Checkpoints are written to disk every this many blocks
+const CHECKPOINT_SAVE_INTERVAL: u64 = 20;
 
-enum FilterType {
-    To,
-    From,
-    FunctionSig,
-    EventHash,
-}
+// How often the operator bond is read for the metrics
+const BOND_POLL_INTERVAL: Duration = Duration::from_secs(60);
 
-enum TransactionType {
-    Unknown,
-    Erc20,
-    Erc721,
-    Deploy,
-}
+// Collections are backfilled this many at a time, and the index is written to disk
+// every this many collections
+const COLLECTION_BACKFILL_CONCURRENCY: usize = 8;
+const COLLECTION_SAVE_INTERVAL: u64 = 100;
+
+// A collection that can't be read is retried this many times before it's skipped, and
+// the backfill runs again this long after skipping any
+const COLLECTION_BACKFILL_ATTEMPTS: usize = 3;
+const COLLECTION_BACKFILL_RETRY_INTERVAL: Duration = Duration::from_secs(600);
 
 struct ReplayFlag {
     replay: Option<String>, // For simplicity, use Option for optional values
@@ -72,19 +109,10 @@ struct NetworkFlag {
     network: Option<String>,
 }
 
-struct TransactionFilter {
-    filter_type: FilterType,
-    match_field: MatchField,
-    network_dependant: bool,
-}
 struct LogMessage {
+    level: LogLevel,
+    context: LogContext,
     msg: String,
-    tag_id: Option<String>,
-}
-
-enum MatchField {
-    SimpleMatch(String),
-    ComplexMatch(std::collections::HashMap<String, String>),
 }
 
 enum ContractType {
@@ -96,74 +124,119 @@ enum ContractType {
 struct BlockJob {
     network: String,
     block: u64,
+    // Hash and parent hash the block had when it was recorded. Only missing for blocks
+    // caught up on that are older than the reorg window, those are fetched by number.
+    hash: Option<H256>,
+    parent_hash: Option<H256>,
+    // Set once the block has enough confirmations to no longer be reorged out.
+    finalized: bool,
+    // Drop event logs of the block. Sales are recorded once the block is final, so a
+    // reorg drops them with the job instead of leaving them counted.
+    drop_logs: Vec<Log>,
+    // Collections deployed by the factory in the block, indexed once it is final.
+    deployments: Vec<Deployment>,
+}
+
+// A collection deployed by the factory, waiting for its block to be final.
+struct Deployment {
+    contract_address: String,
+    deployment_hash: String,
+    deployer: String,
+    transaction_hash: String,
+}
+
+impl BlockJob {
+    // Whether `block` is the block this job was recorded for. Another endpoint may have
+    // been switched to, or the chain reorged, since it was recorded.
+    fn matches<T>(&self, block: &Block<T>) -> bool {
+        block.number == Some(U64::from(self.block))
+            && self.hash.is_none_or(|hash| block.hash == Some(hash))
+            && self.parent_hash.is_none_or(|parent_hash| block.parent_hash == parent_hash)
+    }
+}
+
+// A block that has been fetched and filtered, waiting to be committed in order.
+struct ProcessedBlock {
+    job: BlockJob,
+    interesting_transactions: Vec<InterestingTransaction>,
 }
 
 struct NetworkMonitor {
+    config: Config,
     networks: Vec<String>,
-    providers: HashMap<String, Arc<Provider<Http>>>,
+    providers: HashMap<String, Arc<RpcProvider>>,
     holograph_addresses: HashMap<Environment, Address>,
-    contracts: HashMap<String, ContractInstance<Arc<Provider<Http>>, Provider<Http>>>,
+    contracts: HashMap<String, ContractInstance<Arc<RpcProvider>, RpcProvider>>,
     current_block_height: Arc<Mutex<HashMap<String, u64>>>,
     block_jobs: Arc<Mutex<HashMap<String, Vec<BlockJob>>>>,
+    confirmations: ConfirmationPolicy,
+    receipt_cache: Arc<Mutex<ReceiptCache>>,
+    checkpoints: Checkpoints,
+    // Holographable collections per network, see `backfill_collections`
+    collections: CollectionIndex,
+    logger: Logger,
+    metrics: Arc<Metrics>,
+    health: Arc<Health>,
+    // Cancelled on shutdown, every long running task watches it
+    shutdown: CancellationToken,
+    // Address of the operator wallet, lowercase
+    operator_address: Option<String>,
 
     bloom_filters: BloomFilterMap,
+    transaction_filters: Vec<TransactionFilter>,
 }
 
 impl NetworkMonitor {
-    fn new() -> Self {
+    fn new(config: Config) -> Self {
         let addresses = holograph_addresses();
 
         NetworkMonitor {
-            networks: vec!["optimism".to_string()], // Initialize with optimism
+            networks: config.network_names(),
             providers: HashMap::new(),
             holograph_addresses: addresses,
             contracts: HashMap::new(),
             current_block_height: Arc::new(Mutex::new(HashMap::new())),
             block_jobs: Arc::new(Mutex::new(HashMap::new())),
+            confirmations: ConfirmationPolicy::default(),
+            receipt_cache: Arc::new(Mutex::new(ReceiptCache::default())),
+            checkpoints: Checkpoints::default(),
+            collections: CollectionIndex::default(),
+            logger: Logger::new(config.log_format, config.log_level),
+            metrics: Arc::new(Metrics::default()),
+            health: Arc::new(Health::new(&config.network_names(), config.readiness_stall)),
+            shutdown: CancellationToken::new(),
+            operator_address: None,
 
             bloom_filters: HashMap::new(),
+            transaction_filters: Vec::new(),
+            config,
         }
     }
 
-    async fn init_providers(
-        &mut self,
-        provider_url: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        for network in &self.networks {
-            let provider = Provider::<Http>::connect(provider_url).await;
-            self.providers.insert(network.clone(), Arc::new(provider));
-        }
-        Ok(())
+    // Contracts are loaded from the first configured network
+    fn primary_network(&self) -> &str {
+        &self.config.networks[0].name
     }
 
-    fn get_env() -> Result<Environment, Box<dyn std::error::Error>> {
-        let env_str = std::env::var("HOLOGRAPH_ENV").unwrap_or_else(|_| "develop".to_string());
-        match env_str.as_str() {
-            "localhost" => Ok(Environment::Localhost),
-            "experimental" => Ok(Environment::Experimental),
-            "develop" => Ok(Environment::Develop),
-            "testnet" => Ok(Environment::Testnet),
-            "mainnet" => Ok(Environment::Mainnet),
-            _ => Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Unsupported HOLOGRAPH_ENV value",
-            ))),
+    async fn init_providers(&mut self, tx_logs: &mpsc::Sender<LogMessage>) -> Result<()> {
+        for network in &self.config.networks {
+            let provider = providers::connect(&network.name, &network.rpc_urls, tx_logs.clone())?;
+            self.structured_log(
+                &LogContext::network(&network.name),
+                &format!("Using {} RPC endpoint(s)", network.rpc_urls.len()),
+            );
+            self.providers.insert(network.name.clone(), Arc::new(provider));
         }
+        Ok(())
     }
 
-    async fn fetch_address_from_holograph(
-        &self,
-        name: &str,
-    ) -> Result<Address, Box<dyn std::error::Error>> {
+    async fn fetch_address_from_holograph(&self, name: &str) -> Result<Address> {
         match self.contracts.get("holograph") {
             Some(contract) => {
                 let call = contract.method::<(), Address>(name, ())?;
-                call.call().await.map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+                Ok(call.call().await?)
             }
-            None => Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Holograph contract not found",
-            ))),
+            None => Err(Error::NotFound("Holograph contract not found".to_string())),
         }
     }
 
@@ -171,8 +244,8 @@ impl NetworkMonitor {
         &self,
         abi_str: &str,
         address: Address,
-        provider: Arc<Provider<Http>>,
-    ) -> Result<Contract<Provider<Http>>, Box<dyn std::error::Error>> {
+        provider: Arc<RpcProvider>,
+    ) -> Result<Contract<RpcProvider>> {
         let abi: Abi = serde_json::from_str(abi_str)?;
         Ok(Contract::new(address, abi, provider))
     }
@@ -181,15 +254,13 @@ impl NetworkMonitor {
         &mut self,
         env: &Environment,
         abis: &ContractAbis,
-        provider_arc: &Arc<Provider<Http>>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+        provider_arc: &Arc<RpcProvider>,
+    ) -> Result<()> {
         // Get and store the holograph contract
-        let holograph_address = self.holograph_addresses.get(env).ok_or_else(|| {
-            Box::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Holograph address not found",
-            ))
-        })?;
+        let holograph_address = self
+            .holograph_addresses
+            .get(env)
+            .ok_or_else(|| Error::NotFound("Holograph address not found".to_string()))?;
         let holograph = self
             .create_contract(abis.holograph_abi, holograph_address.clone(), provider_arc.clone())
             .await?;
@@ -217,30 +288,122 @@ impl NetworkMonitor {
         Ok(())
     }
 
-    async fn initialize_ethers(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Get the provider URL from environment variables
-        let provider_url = std::env::var("PROVIDER_URL")?;
+    // Check the Holograph and LayerZero IDs of every configured network against
+    // `HolographInterfaces.getChainId`, reporting every mismatch at once.
+    async fn verify_chain_ids(&self) -> Result<()> {
+        let interfaces = self
+            .contracts
+            .get("interfaces")
+            .ok_or_else(|| Error::NotFound("Interfaces contract not found".to_string()))?;
+
+        let mut mismatches = Vec::new();
+        for name in &self.networks {
+            // Configured networks are validated against the table when loading the config
+            let Some(network) = networks::get(name) else {
+                continue;
+            };
+            let chain_id = U256::from(network.chain_id);
+            let expected = [
+                (ChainIdType::Holograph, "Holograph", U256::from(network.holograph_id)),
+                (ChainIdType::LayerZero, "LayerZero", U256::from(network.layer_zero_id)),
+            ];
+            for (to_type, label, expected_id) in expected {
+                let actual_id: U256 = interfaces
+                    .method("getChainId", (ChainIdType::Evm as u8, chain_id, to_type as u8))?
+                    .call()
+                    .await?;
+                if actual_id != expected_id {
+                    mismatches.push(format!(
+                        "{} {} ID is {} but HolographInterfaces says {}",
+                        name, label, expected_id, actual_id
+                    ));
+                }
+            }
 
-        // Initialize providers
-        self.init_providers(&provider_url).await?;
+            self.structured_log(
+                &LogContext::network(name),
+                &format!(
+                    "{} is chain {} ({}, {}), Holograph ID {}, LayerZero ID {}",
+                    name,
+                    network.chain_id,
+                    if network.testnet { "testnet" } else { "mainnet" },
+                    network.native_token,
+                    network.holograph_id,
+                    network.layer_zero_id
+                ),
+            );
+        }
 
-        // Fetch the provider for "optimism"
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Config(format!("Network table is out of date: {}", mismatches.join("; "))))
+        }
+    }
+
+    async fn initialize_ethers(&mut self, tx_logs: &mpsc::Sender<LogMessage>) -> Result<()> {
+        // Initialize providers from the configured RPC URLs
+        self.init_providers(tx_logs).await?;
+
+        // Refuse to start if any RPC endpoint serves another chain than its network
+        let mut mismatches = Vec::new();
+        for (name, provider) in &self.providers {
+            if let Some(network) = networks::get(name) {
+                let client: &providers::FailoverClient = (**provider).as_ref();
+                mismatches.extend(client.verify_chain_id(network.chain_id).await);
+            }
+        }
+        if !mismatches.is_empty() {
+            return Err(Error::Config(format!("Wrong chain: {}", mismatches.join("; "))));
+        }
+
+        // Load the number of confirmations required before a block counts as final
+        self.confirmations = ConfirmationPolicy::from_config(&self.config.networks);
+        for network in &self.networks {
+            self.structured_log(
+                &LogContext::network(network),
+                &format!(
+                    "Blocks on {} are final after {} confirmations",
+                    network,
+                    self.confirmations.required(network)
+                ),
+            );
+        }
+
+        // Load the last committed block of every network
+        self.checkpoints = Checkpoints::load(&self.config.checkpoint_file)?;
+        for network in &self.networks {
+            if let Some(block) = self.checkpoints.get(network) {
+                self.structured_log(
+                    &LogContext::network(network).block(block),
+                    &format!("Resuming {} from checkpoint at block {}", network, block),
+                );
+            }
+        }
+        self.collections = CollectionIndex::load(&self.config.collections_file)?;
+
+        // Fetch the provider of the primary network
+        let primary_network = self.primary_network().to_string();
         let provider_arc = self
             .providers
-            .get(&"optimism".to_string())
-            .ok_or_else(|| {
-                Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "Provider not found"))
-            })
+            .get(&primary_network)
+            .ok_or_else(|| Error::Config("Provider not found".to_string()))
             .map(|arc| arc.clone())?;
 
         // Get the environment and contract abis
-        let holograph_env = Self::get_env()?;
-        let env_str = std::env::var("HOLOGRAPH_ENV").unwrap_or_else(|_| "develop".to_string());
-        let abis = get_abis(&env_str);
+        let holograph_env = self.config.environment;
+        let abis = get_abis(holograph_env.as_str());
 
         // Initialize contracts
         self.init_contracts(&holograph_env, &abis, &provider_arc).await?;
 
+        // Make sure our chain IDs agree with the ones the contracts use
+        self.verify_chain_ids().await?;
+        self.health.set_contracts_loaded();
+
+        // Build the filters used to pick interesting transactions out of each block
+        self.transaction_filter_builder();
+
         // Print addresses directly from the contracts HashMap
         let contract_names = vec![
             "holograph",
@@ -257,8 +420,8 @@ impl NetworkMonitor {
                 let capitalized_name =
                     name.chars().nth(0).unwrap_or_default().to_uppercase().to_string() + &name[1..]; // Capitalize the contract name here
                 self.structured_log(
+                    &LogContext::network(&primary_network),
                     &format!("📄 {}: {:?}", capitalized_name, contract.address()),
-                    None,
                 );
             }
         }
@@ -268,8 +431,8 @@ impl NetworkMonitor {
             let messaging_module_address: Address =
                 operator_contract.method("getMessagingModule", ())?.call().await?;
             self.structured_log(
+                &LogContext::network(&primary_network),
                 &format!("📄 Messaging Module: {:?}", messaging_module_address),
-                None,
             );
         }
 
@@ -278,93 +441,57 @@ impl NetworkMonitor {
 
     // Asynchronously subscribe to a specified network.
     async fn network_subscribe(
-        &mut self,
+        &self,
         network: &str,
         tx_logs: mpsc::Sender<LogMessage>,
         tx_blocks: mpsc::Sender<BlockJob>, // Change the type to BlockJob
     ) {
-        // Convert the network argument to a String.
-        let network_string = network.to_string();
-
         // Check if there's a provider for the given network.
-        if let Some(provider) = self.providers.get(&network_string) {
-            // Clone the provider to use inside the async block.
-            let provider_clone = provider.clone();
-
-            // Clone the Arc for the current block height to use inside the async block.
-            let current_block_height = self.current_block_height.clone();
-
-            // Spawn a new asynchronous task.
-            tokio::spawn(async move {
-                // Get an asynchronous stream of blocks from the provider.
-                let mut stream =
-                    provider_clone.watch_blocks().await.expect("Failed to watch blocks");
-
-                // Initialize a mutable option for the last block number seen.
-                let mut last_block: Option<u64> = None;
-
-                // Continuously get the next block hash from the stream.
-                while let Some(new_block_hash) = stream.next().await {
-                    // Fetch block details using the block hash.
-                    let block_opt = provider_clone
-                        .get_block(new_block_hash)
-                        .await
-                        .expect("Failed to get block details");
+        if let Some(provider) = self.providers.get(network) {
+            let subscription =
+                BlockSubscription::new(self, network, provider.clone(), tx_logs, tx_blocks);
 
-                    // Extract the block number from the block.
-                    let current_block_u64 = if let Some(actual_block) = block_opt.clone() {
-                        actual_block.number.unwrap_or(U64::from(0)).as_u64()
-                    } else {
-                        // Handle or log cases where the block details couldn't be fetched.
-                        // For now, we simply continue to the next iteration.
-                        continue;
-                    };
-
-                    // If there's a previously seen block...
-                    if let Some(lb) = last_block {
-                        // ...and it's the same as the current block, skip processing.
-                        if lb == current_block_u64 {
-                            continue;
-                        }
-
-                        // If the last block seen and the current block have a gap...
-                        if lb + 1 < current_block_u64 {
-                            // ...log a message about the connection drop.
-                            let log_msg = format!(
-                                "Resuming previously dropped connection, gotta do some catching up. Block: {}",
-                                current_block_u64
-                            );
-                            let _ = tx_logs.send(LogMessage { msg: log_msg, tag_id: None }).await;
-
-                            // TODO: We're not storing jobs in a Vec anymore, so we need to
-                            // think about how to handle missed blocks.
-                        }
-                    }
-                    // Update the last block to the current block.
-                    last_block = Some(current_block_u64);
-
-                    // Update the current block height in a thread-safe manner.
-                    let mut cbh = current_block_height.lock().await;
-                    cbh.insert(network_string.clone(), current_block_u64);
-
-                    // Instead of adding the job to a vector, send it directly to the channel.
-                    let block_job =
-                        BlockJob { network: network_string.clone(), block: current_block_u64 };
-                    let _ = tx_blocks.send(block_job).await;
-
-                    // Log that a new block has been mined.
-                    let log_msg = format!(
-                        "A new block has been mined. New block height is [{}]",
-                        current_block_u64
-                    );
-                    let _ = tx_logs.send(LogMessage { msg: log_msg, tag_id: None }).await;
-                }
-            });
+            // Spawn a supervised task that keeps the block stream alive until shutdown.
+            tokio::spawn(subscription.run());
         }
     }
 
-    async fn process_block(&self, job: BlockJob) {
-        let mut interesting_transactions: Vec<InterestingTransaction> = Vec::new();
+    // Fetch a block with its logs and receipts, retrying failures. Returns None when the
+    // block was reorged out since it was recorded. This only reads shared state, so
+    // several blocks of the same network can be fetched at the same time.
+    async fn fetch_block(&self, job: BlockJob) -> Result<Option<ProcessedBlock>> {
+        let policy = RetryPolicy::default();
+        let context = LogContext::network(&job.network).block(job.block);
+        let interesting_transactions = retry::retry(
+            &policy,
+            || self.fetch_interesting_transactions(&job),
+            |attempt, class, e| {
+                self.metrics.record_error(&job.network, e.kind());
+                self.structured_log_error(
+                    &context,
+                    &format!(
+                        "Attempt {}/{} to process block {} failed ({:?}): {}",
+                        attempt, policy.attempts, job.block, class, e
+                    ),
+                );
+            },
+        )
+        .await?;
+
+        Ok(interesting_transactions
+            .map(|interesting_transactions| ProcessedBlock { job, interesting_transactions }))
+    }
+
+    // Fetch a block and pick out the transactions matching the filters, with their
+    // receipts attached.
+    async fn fetch_interesting_transactions(
+        &self,
+        job: &BlockJob,
+    ) -> Result<Option<Vec<InterestingTransaction>>> {
+        let context = LogContext::network(&job.network).block(job.block);
+        let provider = self.providers.get(&job.network).ok_or_else(|| {
+            Error::Config(format!("Provider not found for network {}", job.network))
+        })?;
 
         // TODO: `self.activated` is a HashMap<String, bool> to track network activation status
         // self.activated.insert(job.network.clone(), true);
@@ -372,69 +499,598 @@ impl NetworkMonitor {
         // TODO: update this to use the new structured logging and send to the log channel
         // self.structured_log(&job.network, "Getting block 🔍", job.block);
 
-        if let Some(provider) = self.providers.get(&job.network) {
-            let block_with_txs = provider.get_block_with_txs(U64::from(job.block)).await;
-
-            match block_with_txs {
-                Ok(Some(block)) => {
-                    // Printing basic information about the block
-                    println!("Block Number: {:?}", block.number);
-                    println!("Block Hash: {:?}", block.hash);
-                    println!("Parent Hash: {:?}", block.parent_hash);
-                    println!("Number of Transactions: {}", block.transactions.len());
-
-                    // Check if the block is recent
-                    let current_height = self
-                        .current_block_height
-                        .lock()
-                        .await
-                        .get(&job.network)
-                        .cloned()
-                        .unwrap_or_default();
-                    let is_recent_block = current_height.wrapping_sub(job.block) < 5;
-
-                    // TODO: function update_gas_pricing to update the gas prices based on the current block
-                    if is_recent_block {
-                        // self.gas_prices.insert(job.network.clone(), update_gas_pricing(&job.network, &block));
+        let block = match job.hash {
+            Some(hash) => {
+                self.metrics
+                    .time_rpc(&job.network, "eth_getBlockByHash", provider.get_block_with_txs(hash))
+                    .await?
+            }
+            None => {
+                self.metrics
+                    .time_rpc(
+                        &job.network,
+                        "eth_getBlockByNumber",
+                        provider.get_block_with_txs(U64::from(job.block)),
+                    )
+                    .await?
+            }
+        };
+        let block = match block {
+            Some(block) if job.matches(&block) => block,
+            Some(block) => {
+                return Err(Error::NotFound(format!(
+                    "Block {} returned by the provider is not the recorded block: got {:?} with parent {:?}",
+                    job.block, block.hash, block.parent_hash
+                )));
+            }
+            None => {
+                // A recorded block that is no longer served was reorged out, unless the
+                // endpoint hasn't seen it yet
+                if job.hash.is_some() {
+                    let canonical = self
+                        .metrics
+                        .time_rpc(
+                            &job.network,
+                            "eth_getBlockByNumber",
+                            provider.get_block(U64::from(job.block)),
+                        )
+                        .await?;
+                    if let Some(canonical) =
+                        canonical.filter(|canonical| canonical.hash != job.hash)
+                    {
+                        self.log(
+                            LogLevel::Warn,
+                            &context.clone().tag(Some("reorg")),
+                            &format!(
+                                "Skipping block {} {:?}, it was reorged out by {:?}",
+                                job.block, job.hash, canonical.hash
+                            ),
+                        );
+                        return Ok(None);
                     }
+                }
+                return Err(Error::NotFound(format!(
+                    "Block {} was not returned by the provider",
+                    job.block
+                )));
+            }
+        };
+
+        // Printing basic information about the block
+        self.log(
+            LogLevel::Debug,
+            &context,
+            &format!(
+                "Block Number: {:?} Block Hash: {:?} Parent Hash: {:?} Number of Transactions: {}",
+                block.number,
+                block.hash,
+                block.parent_hash,
+                block.transactions.len()
+            ),
+        );
+
+        // Check if the block is recent, i.e. still within the confirmation depth
+        let current_height =
+            self.current_block_height.lock().await.get(&job.network).cloned().unwrap_or_default();
+        let finality = self.confirmations.finality(&job.network, job.block, current_height);
+        let is_recent_block = finality != BlockFinality::Final;
+
+        if let BlockFinality::Provisional { confirmations, required } = finality {
+            self.log(
+                LogLevel::Debug,
+                &context,
+                &format!(
+                    "Block {} is provisional ({}/{} confirmations)",
+                    job.block, confirmations, required
+                ),
+            );
+        }
+
+        // TODO: function update_gas_pricing to update the gas prices based on the current block
+        if is_recent_block {
+            // self.gas_prices.insert(job.network.clone(), update_gas_pricing(&job.network, &block));
+        }
+
+        // Only fetch logs when the block bloom says there may be events we care about.
+        // A single attempt, the whole block is retried on failure.
+        let mut logs = Vec::new();
+        if let Some(bloom) = &block.logs_bloom {
+            if check_bloom_logs(bloom, &job.network, &self.transaction_filters) {
+                let params = LogsParams {
+                    network: job.network.clone(),
+                    from_block: job.block,
+                    to_block: Some(job.block),
+                    tags: None,
+                    attempts: Some(1),
+                    can_fail: Some(false),
+                    interval: None,
+                };
+                logs = self.get_logs(params).await?;
+            }
+        }
+
+        let mut interesting_transactions = filter_transactions(
+            &job.network,
+            &self.transaction_filters,
+            &block.transactions,
+            &logs,
+        );
+        // Drop events are matched on any contract, only indexed drops are kept
+        interesting_transactions.retain(|interesting| {
+            match interesting.log.as_ref().and_then(decode_log) {
+                Some(event) if event.is_drop_event() => {
+                    self.collections.find_by_source(&job.network, &event.base().contract).is_some()
+                }
+                _ => true,
+            }
+        });
+
+        // Attach receipts, logs and decoded events so handlers get full context
+        if let (false, Some(block_hash)) = (interesting_transactions.is_empty(), block.hash) {
+            enrich_transactions(
+                provider,
+                &self.receipt_cache,
+                &self.metrics,
+                &job.network,
+                block_hash,
+                &mut interesting_transactions,
+            )
+            .await?;
+        }
+
+        Ok(Some(interesting_transactions))
+    }
+
+    // Commit a fetched block. Blocks of a network are committed one at a time and in
+    // block order, so the checkpoint only ever covers blocks that were fully handled.
+    async fn commit_block(&self, processed: ProcessedBlock) {
+        let ProcessedBlock { mut job, interesting_transactions } = processed;
+
+        // The subscription records blocks below a new one before the new one, so a job
+        // that doesn't build on the block committed below it was reorged out while it
+        // was queued. The blocks that replaced it are queued after it.
+        if let Some(parent_hash) = job.parent_hash {
+            let block_jobs = self.block_jobs.lock().await;
+            let committed_parent = block_jobs
+                .get(&job.network)
+                .and_then(|jobs| jobs.iter().find(|recorded| recorded.block + 1 == job.block))
+                .and_then(|recorded| recorded.hash);
+            if committed_parent.is_some_and(|hash| hash != parent_hash) {
+                drop(block_jobs);
+                self.log(
+                    LogLevel::Warn,
+                    &LogContext::network(&job.network).block(job.block).tag(Some("reorg")),
+                    &format!("Skipping block {} {:?}, it was reorged out", job.block, job.hash),
+                );
+                return;
+            }
+        }
+
+        for interesting in &interesting_transactions {
+            let matched = match &interesting.log {
+                Some(log) => format!("log {}", log.log_index.unwrap_or_default()),
+                None => "transaction".to_string(),
+            };
+            self.structured_log(
+                &LogContext::network(&job.network)
+                    .block(job.block)
+                    .job_hash(interesting.job_hash())
+                    .tag(Some(&interesting.bloom_id)),
+                &format!(
+                    "Found {:?} transaction {:?} ({} matched) with {} events in block {}",
+                    interesting.transaction_type,
+                    interesting.transaction.hash,
+                    matched,
+                    interesting.events.len(),
+                    job.block
+                ),
+            );
+        }
+        self.record_transaction_metrics(&job.network, &interesting_transactions);
+        job.deployments = self.factory_deployments(&job, &interesting_transactions);
+        job.drop_logs = interesting_transactions
+            .iter()
+            .filter_map(|interesting| interesting.log.clone())
+            .filter(|log| decode_log(log).is_some_and(|event| event.is_drop_event()))
+            .collect();
+        // self.process_transactions2(&job, &interesting_transactions).await;
+
+        // TODO: a block job handler to handle jobs after processing blocks
+        // self.block_job_handler(&job).await;
+
+        // Advance the checkpoint so processing resumes after this block on restart.
+        self.checkpoints.set(&job.network, job.block);
+        self.metrics.set_processed_block(&job.network, job.block);
+        if job.block % CHECKPOINT_SAVE_INTERVAL == 0 {
+            if let Err(e) = self.checkpoints.save() {
+                self.metrics.record_error(&job.network, e.kind());
+                self.structured_log_error(
+                    &LogContext::network(&job.network).block(job.block),
+                    &e.to_string(),
+                );
+            }
+        }
+
+        // Keep track of recently processed blocks so they can be rolled back on a reorg.
+        let head =
+            self.current_block_height.lock().await.get(&job.network).cloned().unwrap_or_default();
+        let network = job.network.clone();
+        let mut block_jobs = self.block_jobs.lock().await;
+        let network_jobs = block_jobs.entry(network.clone()).or_default();
+        network_jobs.retain(|recorded| recorded.block != job.block);
+        network_jobs.push(job);
+
+        // Promote provisional blocks that are now buried deep enough, then index their
+        // collections and record their drop sales outside the lock.
+        let mut final_blocks = Vec::new();
+        for recorded in network_jobs.iter_mut().filter(|recorded| !recorded.finalized) {
+            if self.confirmations.finality(&recorded.network, recorded.block, head)
+                == BlockFinality::Final
+            {
+                recorded.finalized = true;
+                self.structured_log(
+                    &LogContext::network(&recorded.network).block(recorded.block),
+                    &format!("Block {} is final", recorded.block),
+                );
+                if !recorded.drop_logs.is_empty() || !recorded.deployments.is_empty() {
+                    final_blocks.push((
+                        recorded.block,
+                        std::mem::take(&mut recorded.deployments),
+                        std::mem::take(&mut recorded.drop_logs),
+                    ));
+                }
+            }
+        }
 
-                    // Check bloom logs and fetch logs if present. TODO: implement check_bloom_logs
-                    // if self.check_bloom_logs(&block) {
-                    //     let logs = provider.get_logs(Filter {
-                    //         from_block: Some(job.block.into()),
-                    //         to_block: Some(job.block.into()),
-                    //         ..Default::default()
-                    //     }).await;
-
-                    //     match logs {
-                    //         Ok(logs_list) => {
-                    //             // TODO: sort and filter the logs and process the transactions
-                    //             // self.filter_transactions(&job, &block.transactions, &logs_list, &mut interesting_transactions);
-                    //         }
-                    //         Err(e) => {
-                    //             // Handle error while fetching logs
-                    //         }
-                    //     }
-                    // }
-
-                    // If there are interesting transactions, process them
-                    if !interesting_transactions.is_empty() {
-                        // self.process_transactions2(&job, &interesting_transactions).await;
+        // Only final jobs leave the window, the others still hold what they record once
+        // they are final
+        while network_jobs.len() > reorg::BLOCK_HISTORY_WINDOW
+            && network_jobs.first().is_some_and(|recorded| recorded.finalized)
+        {
+            network_jobs.remove(0);
+        }
+        drop(block_jobs);
+        for (block, deployments, logs) in final_blocks {
+            self.index_deployed_collections(&network, block, &deployments).await;
+            self.record_drop_sales(&network, block, &logs).await;
+        }
+    }
+
+    // Count operator jobs and the gas spent by our own transactions.
+    fn record_transaction_metrics(
+        &self,
+        network: &str,
+        interesting_transactions: &[InterestingTransaction],
+    ) {
+        // Protocol contracts have the same address on every network
+        let operator_contract =
+            self.contracts.get("operator").map(|operator| format!("{:?}", operator.address()));
+        let (outcomes, gas_spent) = transaction_metrics(
+            interesting_transactions,
+            operator_contract.as_deref(),
+            self.operator_address.as_deref(),
+        );
+        for outcome in outcomes {
+            self.metrics.record_job(network, outcome);
+        }
+        if !gas_spent.is_zero() {
+            self.metrics.add_gas_spent(network, gas_spent);
+        }
+    }
+
+    // Read the amount bonded by our operator, for the metrics.
+    async fn update_bond_amount(&self) -> Result<()> {
+        let (Some(operator), Some(address)) =
+            (self.contracts.get("operator"), self.operator_address.as_deref())
+        else {
+            return Ok(());
+        };
+        let address: Address = address
+            .parse()
+            .map_err(|_| Error::Config(format!("Invalid operator address: {}", address)))?;
+
+        // Contracts are loaded from the primary network, see `initialize_ethers`
+        let network = self.primary_network();
+        let amount: U256 = self
+            .metrics
+            .time_rpc(network, "eth_call", operator.method("getBondedAmount", address)?.call())
+            .await?;
+        self.metrics.set_bond_amount(network, amount);
+        Ok(())
+    }
+
+    // Index the holographable contracts in the registry of a network that aren't indexed
+    // yet. Runs alongside block processing, collections deployed meanwhile are picked up
+    // from their factory event and skipped here. Collections that keep failing are
+    // skipped, and the registry length stays before the first of them so the next
+    // backfill tries them again.
+    async fn backfill_collections(&self, network: &str) -> Result<()> {
+        let (Some(registry), Some(factory), Some(provider)) = (
+            self.contracts.get("registry"),
+            self.contracts.get("factory"),
+            self.providers.get(network),
+        ) else {
+            return Ok(());
+        };
+        let abis = get_abis(self.config.environment.as_str());
+
+        // Protocol contracts have the same address on every network
+        let registry = Contract::new(
+            registry.address(),
+            serde_json::from_str::<Abi>(abis.holograph_registry_abi)?,
+            provider.clone(),
+        );
+        let from = self.collections.registry_length(network);
+        let addresses = collections::holographable_contracts(&registry, from).await?;
+        if addresses.is_empty() {
+            return Ok(());
+        }
+
+        let policy = RetryPolicy::new(COLLECTION_BACKFILL_ATTEMPTS, Duration::from_secs(1));
+        let context = LogContext::network(network);
+        let mut indexed = from;
+        // Registry position of the first skipped collection, and its error
+        let mut first_failure: Option<(u64, Error)> = None;
+        let mut skipped = 0;
+        let mut entries = futures::stream::iter(addresses)
+            .map(|address| {
+                let (abis, policy) = (&abis, &policy);
+                async move {
+                    let key = format!("{:?}", address);
+                    if self.collections.contains(network, &key) {
+                        return (key, None);
                     }
+                    let collection = self
+                        .retry(network, policy, || {
+                            collections::backfill_collection(
+                                provider,
+                                abis,
+                                factory.address(),
+                                address,
+                            )
+                        })
+                        .await
+                        .map_err(Error::from);
+                    (key, Some(collection))
                 }
-                Ok(None) => {
-                    // This case means the provider returned a successful result, but no block was found.
-                    println!("No block was returned for block number {}", job.block);
+            })
+            .buffered(COLLECTION_BACKFILL_CONCURRENCY);
+        let registry_length = |indexed: u64, first_failure: &Option<(u64, Error)>| {
+            first_failure.as_ref().map_or(indexed, |(position, _)| *position)
+        };
+        while let Some((address, collection)) = entries.next().await {
+            if self.shutdown.is_cancelled() {
+                break;
+            }
+            match collection {
+                Some(Ok(collection)) => self.collections.insert(network, &address, collection),
+                Some(Err(e)) => {
+                    self.metrics.record_error(network, e.kind());
+                    self.structured_log_error(
+                        &context,
+                        &format!("Skipping collection {}: {}", address, e),
+                    );
+                    skipped += 1;
+                    first_failure.get_or_insert((indexed, e));
                 }
+                None => {}
+            }
+            indexed += 1;
+            if indexed.is_multiple_of(COLLECTION_SAVE_INTERVAL) {
+                self.collections
+                    .set_registry_length(network, registry_length(indexed, &first_failure));
+                self.collections.save()?;
+            }
+        }
+        self.collections.set_registry_length(network, registry_length(indexed, &first_failure));
+        self.collections.save()?;
+
+        self.structured_log(
+            &context,
+            &format!(
+                "Indexed {} holographable contracts on {} ({} in total, {} skipped)",
+                indexed - from - skipped,
+                network,
+                self.collections.len(network),
+                skipped
+            ),
+        );
+        match first_failure {
+            Some((_, e)) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    // Collections deployed by the factory in a block. Every interesting entry of a
+    // transaction carries all of its events, so each transaction is only read once.
+    fn factory_deployments(
+        &self,
+        job: &BlockJob,
+        interesting_transactions: &[InterestingTransaction],
+    ) -> Vec<Deployment> {
+        let Some(factory) = self.contracts.get("factory") else {
+            return Vec::new();
+        };
+        // Protocol contracts have the same address on every network
+        let factory_address = format!("{:?}", factory.address());
+        let context = LogContext::network(&job.network).block(job.block);
+        let mut seen = HashSet::new();
+        let mut deployments = Vec::new();
+
+        for interesting in interesting_transactions {
+            if !seen.insert(interesting.transaction.hash) {
+                continue;
+            }
+            for event in &interesting.events {
+                let DecodedEvent::BridgeableContractDeployed(deployed) = event else {
+                    continue;
+                };
+                // Any contract can emit the event, only the factory's deploy collections
+                if deployed.base.contract != factory_address {
+                    self.log(
+                        LogLevel::Debug,
+                        &context,
+                        &format!(
+                            "Ignoring deployment of {} emitted by {} instead of the factory",
+                            deployed.contract_address, deployed.base.contract
+                        ),
+                    );
+                    continue;
+                }
+                deployments.push(Deployment {
+                    contract_address: deployed.contract_address.clone(),
+                    deployment_hash: deployed.hash.clone(),
+                    deployer: format!("{:?}", interesting.transaction.from),
+                    transaction_hash: format!("{:?}", interesting.transaction.hash),
+                });
+            }
+        }
+        deployments
+    }
+
+    // Index the collections deployed in a final block.
+    async fn index_deployed_collections(
+        &self,
+        network: &str,
+        block: u64,
+        deployments: &[Deployment],
+    ) {
+        let Some(provider) = self.providers.get(network) else {
+            return;
+        };
+        let abis = get_abis(self.config.environment.as_str());
+        let context = LogContext::network(network).block(block);
+
+        for deployment in deployments {
+            let collection = async {
+                let address: Address = deployment.contract_address.parse().map_err(|_| {
+                    Error::Decode(format!(
+                        "Invalid collection address: {}",
+                        deployment.contract_address
+                    ))
+                })?;
+                let collection_type =
+                    collections::collection_type(provider, &abis, address).await?;
+                // Drops are followed through the sale events of their source
+                let source = match collection_type {
+                    CollectionType::Drop => {
+                        Some(collections::source_contract(provider, &abis, address).await?)
+                    }
+                    _ => None,
+                };
+                Ok::<_, Error>((collection_type, source))
+            };
+            let (collection_type, source) = match collection.await {
+                Ok(collection) => collection,
                 Err(e) => {
-                    // Handle error fetching block with transactions
-                    // self.structured_log_error(&job.network, &format!("Error processing block {}", e), job.block);
+                    self.metrics.record_error(network, e.kind());
+                    self.structured_log_error(
+                        &context,
+                        &format!(
+                            "Failed to index collection {}: {}",
+                            deployment.contract_address, e
+                        ),
+                    );
+                    continue;
                 }
+            };
+
+            self.collections.insert(
+                network,
+                &deployment.contract_address,
+                Collection {
+                    collection_type,
+                    deployer: Some(deployment.deployer.clone()),
+                    deploy_block: Some(block),
+                    deployment_hash: Some(deployment.deployment_hash.clone()),
+                    transaction_hash: Some(deployment.transaction_hash.clone()),
+                    source,
+                    sales: None,
+                },
+            );
+            self.structured_log(
+                &context,
+                &format!(
+                    "Indexed new {:?} collection {}",
+                    collection_type, deployment.contract_address
+                ),
+            );
+            if let Err(e) = self.collections.save() {
+                self.metrics.record_error(network, e.kind());
+                self.structured_log_error(&context, &e.to_string());
             }
         }
+    }
 
-        // TODO: a block job handler to handle jobs after processing blocks
-        // self.block_job_handler(&job).await;
+    // Count the sales of indexed drops from the drop event logs of a final block. Only
+    // the log a transaction was matched on is kept, a purchase is matched once for each
+    // of its drop events.
+    async fn record_drop_sales(&self, network: &str, block: u64, logs: &[Log]) {
+        let abis = get_abis(self.config.environment.as_str());
+        let mut recorded = false;
+
+        for log in logs {
+            let Some(event) = decode_log(log) else {
+                continue;
+            };
+            if !event.is_drop_event() {
+                continue;
+            }
+            let source = &event.base().contract;
+            // Other contracts emit events with the same signatures
+            let Some(address) = self.collections.find_by_source(network, source) else {
+                continue;
+            };
+
+            // The new price is read as of the block that changed it
+            let mut public_sale_price = None;
+            if let (DecodedEvent::SalesConfigChanged(_), Some(provider)) =
+                (&event, self.providers.get(network))
+            {
+                let price = async {
+                    let abi: Abi = serde_json::from_str(abis.holograph_drop_erc721_abi)?;
+                    let address: Address = source
+                        .parse()
+                        .map_err(|_| Error::Decode(format!("Invalid drop address: {}", source)))?;
+                    let drop = Contract::new(address, abi, provider.clone());
+                    let config: drops::SalesConfigTuple =
+                        drop.method("salesConfig", ())?.block(block).call().await?;
+                    Ok::<_, Error>(config.0)
+                };
+                match price.await {
+                    Ok(price) => public_sale_price = Some(price),
+                    Err(e) => {
+                        self.metrics.record_error(network, e.kind());
+                        self.structured_log_error(
+                            &LogContext::network(network).block(block),
+                            &format!("Failed to read sales config of {}: {}", address, e),
+                        );
+                    }
+                }
+            }
+
+            let mut counted = false;
+            self.collections.update_sales(network, &address, |sales| {
+                counted = sales.record(block, &event, public_sale_price);
+            });
+            if let (true, Some(description)) = (counted, drops::describe(&event)) {
+                self.structured_log(
+                    &LogContext::network(network).block(block),
+                    &format!("Drop {}: {}", address, description),
+                );
+            }
+            recorded |= counted;
+        }
+
+        if recorded {
+            if let Err(e) = self.collections.save() {
+                self.metrics.record_error(network, e.kind());
+                self.structured_log_error(
+                    &LogContext::network(network).block(block),
+                    &e.to_string(),
+                );
+            }
+        }
     }
 
     fn build_filter(
@@ -518,181 +1174,490 @@ impl NetworkMonitor {
         }
     }
 
-    // Generic retry function
-    async fn retry<F, T>(
-        &self,
-        network: &str,
-        func: F,
-        attempts: usize,
-        interval: u64,
-    ) -> Result<T, Box<dyn std::error::Error>>
-    where
-        F: Fn() -> std::pin::Pin<
-            Box<dyn std::future::Future<Output = Result<T, Box<dyn std::error::Error>>>>,
-        >,
-    {
-        for i in 0..attempts {
-            match func().await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    self.structured_log_error(network, &e.to_string());
-                    if i == attempts - 1 {
-                        return Err(Box::new(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            format!(
-                                "Maximum attempts reached, function did not succeed after {} attempts",
-                                attempts
+    // Build the list of transaction and event filters evaluated against every block
+    fn transaction_filter_builder(&mut self) {
+        self.transaction_filters = transaction_filters(&self.contracts, &self.networks);
+    }
+
+    // Fetch logs for a block range, splitting it into chunks the provider accepts.
+    // Each chunk is retried; if `can_fail` is set a chunk that keeps failing is
+    // skipped instead of failing the whole call.
+    async fn get_logs(&self, params: LogsParams) -> Result<Vec<Log>> {
+        let provider = self.providers.get(&params.network).cloned().ok_or_else(|| {
+            Error::Config(format!("Provider not found for network {}", params.network))
+        })?;
+
+        let to_block = match params.to_block {
+            Some(to_block) => to_block,
+            None => provider.get_block_number().await?.as_u64(),
+        };
+        let policy = RetryPolicy::new(
+            params.attempts.unwrap_or(DEFAULT_LOGS_ATTEMPTS) as usize,
+            Duration::from_millis(params.interval.unwrap_or(DEFAULT_LOGS_INTERVAL)),
+        );
+        let can_fail = params.can_fail.unwrap_or(false);
+        let tags = params.tags.map(|tags| tags.join("] ["));
+        let context = LogContext::network(&params.network).tag(tags.as_deref());
+
+        let mut logs = Vec::new();
+        let mut from_block = params.from_block;
+        while from_block <= to_block {
+            let chunk_end = to_block.min(from_block + MAX_LOGS_BLOCK_RANGE - 1);
+            let filter = Filter::new().from_block(from_block).to_block(chunk_end);
+
+            let result = self
+                .retry(&params.network, &policy, || {
+                    self.metrics.time_rpc(
+                        &params.network,
+                        "eth_getLogs",
+                        provider.get_logs(&filter),
+                    )
+                })
+                .await;
+
+            match result {
+                Ok(chunk_logs) => {
+                    if from_block != chunk_end {
+                        self.structured_log(
+                            &context,
+                            &format!(
+                                "Fetched {} logs for blocks {} to {}",
+                                chunk_logs.len(),
+                                from_block,
+                                chunk_end
                             ),
-                        )));
+                        );
                     }
-                    sleep(Duration::from_millis(interval)).await;
+                    logs.extend(chunk_logs);
                 }
+                Err(e) if can_fail => {
+                    let e = Error::from(e);
+                    self.metrics.record_error(&params.network, e.kind());
+                    self.log(
+                        LogLevel::Warn,
+                        &context,
+                        &format!("Skipping logs for blocks {} to {}: {}", from_block, chunk_end, e),
+                    );
+                }
+                Err(e) => return Err(e.into()),
             }
-        }
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Unexpected error in retry loop",
-        )))
-    }
-
-    fn structured_log(&self, msg: &str, tag_id: Option<&str>) {
-        let timestamp = chrono::Utc::now().format("%+").to_string();
-        let timestamp_color = "green";
 
-        // Inferring the network from the providers.
-        // For simplicity, this is just using the first provider in the providers map.
-        let binding = "unknown".to_string();
-        let network = self.providers.keys().next().unwrap_or(&binding);
-        let network_name =
-            network.chars().nth(0).unwrap_or_default().to_uppercase().to_string() + &network[1..];
+            from_block = chunk_end + 1;
+        }
 
-        let env_name = match Self::get_env() {
-            Ok(env) => format!("{:?}", env),
-            Err(_) => "UnknownEnv".to_string(),
-        };
+        Ok(logs)
+    }
 
-        let tag_string = match tag_id {
-            Some(tag) => format!("[{}] ", tag.to_string()), // Added space after the closing bracket
-            None => "".to_string(),
-        };
+    // Generic retry function, logging every failed attempt against the network
+    async fn retry<F, Fut, T, E>(
+        &self,
+        network: &str,
+        policy: &RetryPolicy,
+        func: F,
+    ) -> std::result::Result<T, RetryError<E>>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = std::result::Result<T, E>>,
+        E: std::fmt::Display + retry::Classify,
+    {
+        retry::retry(policy, func, |attempt, class, e| {
+            self.structured_log_error(
+                &LogContext::network(network),
+                &format!("Attempt {}/{} failed ({:?}): {}", attempt, policy.attempts, class, e),
+            );
+        })
+        .await
+    }
 
-        let log_message = format!(
-            "[{}] [{}] [{}] {}{}",
-            timestamp.color(timestamp_color),
-            network_name.color("red"),
-            env_name.color("cyan"),
-            tag_string,
-            msg.trim_start() // Remove leading whitespaces from the message
-        );
+    fn structured_log(&self, context: &LogContext, msg: &str) {
+        self.log(LogLevel::Info, context, msg);
+    }
 
-        println!("{}", log_message);
+    fn structured_log_error(&self, context: &LogContext, msg: &str) {
+        self.log(LogLevel::Error, context, msg);
     }
 
-    fn structured_log_error(&self, network: &str, msg: &str) {
-        let timestamp = chrono::Utc::now().format("%+").to_string();
-        let timestamp_color = "red"; // Changed to red for error logging
+    fn log(&self, level: LogLevel, context: &LogContext, msg: &str) {
+        let env_name = format!("{:?}", self.config.environment);
 
-        // Using the provided network instead of inferring from the providers
-        let network_name =
-            network.chars().nth(0).unwrap_or_default().to_uppercase().to_string() + &network[1..];
+        self.logger.log(&LogRecord { level, environment: &env_name, context, msg });
+    }
+}
 
-        let env_name = match Self::get_env() {
-            Ok(env) => format!("{:?}", env),
-            Err(_) => "UnknownEnv".to_string(),
-        };
+// Transaction and event filters for the protocol contracts, evaluated in order against
+// every block. The first transaction filter a transaction matches is the one reported.
+fn transaction_filters(
+    contracts: &HashMap<String, ContractInstance<Arc<RpcProvider>, RpcProvider>>,
+    networks: &[String],
+) -> Vec<TransactionFilter> {
+    // Contract addresses can differ per network, so calls to them are matched per network
+    let contract_filter = |name: &str| {
+        contracts.get(name).map(|contract| TransactionFilter {
+            bloom_id: name.to_string(),
+            filter_type: FilterType::To,
+            match_field: MatchField::ComplexMatch(
+                networks
+                    .iter()
+                    .map(|network| (network.clone(), format!("{:?}", contract.address())))
+                    .collect(),
+            ),
+            network_dependant: true,
+        })
+    };
+
+    let mut filters: Vec<TransactionFilter> =
+        ["bridge", "operator"].iter().filter_map(|name| contract_filter(name)).collect();
+
+    // Job executions are matched by selector so they are found even when relayed by
+    // another contract
+    filters.push(TransactionFilter {
+        bloom_id: "executeJob".to_string(),
+        filter_type: FilterType::FunctionSig,
+        match_field: MatchField::SimpleMatch(format!(
+            "0x{}",
+            ethers::utils::hex::encode(ethers::utils::id("executeJob(bytes)"))
+        )),
+        network_dependant: false,
+    });
 
-        // For errors we're prepending the tag with [ERROR]
-        let tag_string = format!("[ERROR] ");
+    // Protocol events are identified by their topic hash
+    let events = vec![
+        EventType::BridgeableContractDeployed,
+        EventType::CrossChainMessageSent,
+        EventType::AvailableOperatorJob,
+        EventType::FinishedOperatorJob,
+        EventType::FailedOperatorJob,
+        EventType::SaleDrop,
+        EventType::MintFeePayoutDrop,
+        EventType::FundsWithdrawnDrop,
+        EventType::SalesConfigChangedDrop,
+        EventType::OpenMintFinalizedDrop,
+    ];
+    for event_type in events {
+        if let Some(sig_hash) = event_type.sig_hash() {
+            filters.push(TransactionFilter {
+                bloom_id: format!("{:?}", event_type),
+                filter_type: FilterType::EventHash,
+                match_field: MatchField::SimpleMatch(format!("{:?}", sig_hash)),
+                network_dependant: false,
+            });
+        }
+    }
 
-        let log_message = format!(
-            "[{}] [{}] [{}] {}{}",
-            timestamp.color(timestamp_color),
-            network_name.color("blue"),
-            env_name.color("cyan"),
-            tag_string,
-            msg.trim_start() // Remove leading whitespaces from the message
-        );
+    filters
+}
 
-        println!("{}", log_message);
+// Matches the transactions sent from our own wallet. It goes after the protocol
+// filters, so our protocol calls are still reported as such.
+fn wallet_filter(address: String) -> TransactionFilter {
+    TransactionFilter {
+        bloom_id: "wallet".to_string(),
+        filter_type: FilterType::From,
+        match_field: MatchField::SimpleMatch(address),
+        network_dependant: false,
     }
 }
 
-async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().ok(); // Load environment variables from .env file
-    let test_address = std::env::var("TEST_ADDRESS").expect("TEST_ADDRESS not set in environment");
-
-    let monitor = Arc::new(Mutex::new(NetworkMonitor::new()));
-
-    // Create channels for log and block messages
-    let (tx_logs, mut rx_logs) = mpsc::channel(32);
-    let (tx_blocks, mut rx_blocks) = mpsc::channel(32);
+// Operator job outcomes and gas spent by `wallet` in a block's interesting transactions.
+// A transaction is matched once per interesting log, but only counted once, and only
+// events emitted by the operator contract count: any contract can emit events with the
+// same signature.
+fn transaction_metrics(
+    interesting_transactions: &[InterestingTransaction],
+    operator_contract: Option<&str>,
+    wallet: Option<&str>,
+) -> (Vec<JobOutcome>, U256) {
+    let mut counted = HashSet::new();
+    let mut outcomes = Vec::new();
+    let mut gas_spent = U256::zero();
+
+    for interesting in interesting_transactions {
+        if !counted.insert(interesting.transaction.hash) {
+            continue;
+        }
 
-    {
-        let mut monitor_guard = monitor.lock().await;
+        for event in &interesting.events {
+            if Some(event.base().contract.as_str()) != operator_contract {
+                continue;
+            }
+            outcomes.push(match event {
+                DecodedEvent::AvailableOperatorJob(_) => JobOutcome::Seen,
+                DecodedEvent::FinishedOperatorJob(finished) => {
+                    if wallet == Some(finished.operator.as_str()) {
+                        JobOutcome::Executed
+                    } else {
+                        JobOutcome::Skipped
+                    }
+                }
+                DecodedEvent::FailedOperatorJob(_) => JobOutcome::Failed,
+                _ => continue,
+            });
+        }
 
-        if let Err(e) = monitor_guard.initialize_ethers().await {
-            monitor_guard.structured_log(&format!("Error initializing Ethers: {:?}", e), None);
-            return Err(e.into());
+        // Whichever filter matched first, our own transactions are those we sent
+        if wallet == Some(format!("{:?}", interesting.transaction.from).as_str()) {
+            if let Some(receipt) = &interesting.receipt {
+                let gas_used = receipt.gas_used.unwrap_or_default();
+                let gas_price = receipt.effective_gas_price.unwrap_or_default();
+                gas_spent += gas_used * gas_price;
+            }
         }
+    }
 
-        // Get the provider for the network from the monitor for other tasks to use
-        let provider = match monitor_guard.providers.get("optimism") {
-            Some(p) => p,
-            None => {
-                monitor_guard.structured_log("Couldn't find the provider for the network.", None);
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Provider not found",
-                )));
+    (outcomes, gas_spent)
+}
+
+async fn run() -> Result<()> {
+    dotenv().ok(); // Load environment variables from .env file
+    let config = Config::load()?;
+    let operator_address = config.wallet.address()?;
+
+    let mut monitor = NetworkMonitor::new(config);
+    monitor.structured_log(
+        &LogContext::default(),
+        &format!(
+            "Operator mode {:?}, gas price multiplier {}, max gas price {}, min profit {} gwei",
+            monitor.config.operator_mode,
+            monitor.config.gas.gas_price_multiplier,
+            monitor
+                .config
+                .gas
+                .max_gas_price_gwei
+                .map_or("unlimited".to_string(), |max| format!("{} gwei", max)),
+            monitor.config.gas.min_profit_gwei
+        ),
+    );
+
+    // Create the channel for log messages
+    let (tx_logs, mut rx_logs) = mpsc::channel::<LogMessage>(32);
+
+    // Optional HTTP server for the operational endpoints. It starts before the contracts
+    // are loaded so a supervisor can tell the monitor is up but not ready yet. The
+    // monitor isn't shared yet, so a failure is reported through the log channel.
+    if let Some(http_addr) = monitor.config.http_addr {
+        let endpoints = Arc::new(Endpoints {
+            metrics: monitor.metrics.clone(),
+            health: monitor.health.clone(),
+            current_block_height: monitor.current_block_height.clone(),
+        });
+        let metrics = monitor.metrics.clone();
+        let network = monitor.primary_network().to_string();
+        let tx_logs = tx_logs.clone();
+        tokio::spawn(async move {
+            if let Err(e) = http::serve(&http_addr.to_string(), endpoints).await {
+                metrics.record_error(&network, e.kind());
+                let _ = tx_logs
+                    .send(LogMessage {
+                        level: LogLevel::Error,
+                        context: LogContext::default().tag(Some("http")),
+                        msg: format!("HTTP server on {} failed: {}", http_addr, e),
+                    })
+                    .await;
             }
-        };
+        });
+    }
+
+    if let Err(e) = monitor.initialize_ethers(&tx_logs).await {
+        monitor.structured_log_error(
+            &LogContext::default(),
+            &format!("Error initializing Ethers [{}]: {}", e.kind(), e),
+        );
+        return Err(e);
+    }
 
-        // Start block monitoring for "optimism" network and pass both channels
-        monitor_guard.network_subscribe("optimism", tx_logs.clone(), tx_blocks.clone()).await;
+    // Keep track of transactions sent from our own wallet
+    if let Some(address) = operator_address {
+        let address = format!("{:?}", address);
+        monitor.operator_address = Some(address.clone());
+        monitor.transaction_filters.push(wallet_filter(address));
     }
 
+    // From here on the monitor is only read. Mutable state lives behind its own locks,
+    // so tasks never wait on each other for the monitor itself.
+    let monitor = Arc::new(monitor);
+
     // Dedicated task for handling log messages
     let monitor_for_log_task = monitor.clone();
     tokio::spawn(async move {
         while let Some(log_msg) = rx_logs.recv().await {
-            let monitor_guard = monitor_for_log_task.lock().await;
-            monitor_guard.structured_log(&log_msg.msg, log_msg.tag_id.as_deref());
+            monitor_for_log_task.log(log_msg.level, &log_msg.context, &log_msg.msg);
         }
     });
 
-    // Dedicated task for handling block messages
-    let monitor_for_block_task = monitor.clone();
-    tokio::spawn(async move {
-        while let Some(block_job) = rx_blocks.recv().await {
-            // For now, this just processes the block job.
-            let monitor_guard = monitor_for_block_task.lock().await;
-            monitor_guard.process_block(block_job).await;
-        }
-    });
+    // Index the holographable collections of every network in the background
+    for network in monitor.networks.clone() {
+        let monitor = monitor.clone();
+        tokio::spawn(async move {
+            loop {
+                let Err(e) = monitor.backfill_collections(&network).await else {
+                    return;
+                };
+                monitor.metrics.record_error(&network, e.kind());
+                monitor.structured_log_error(
+                    &LogContext::network(&network),
+                    &format!(
+                        "Error backfilling collections, retrying in {}s: {}",
+                        COLLECTION_BACKFILL_RETRY_INTERVAL.as_secs(),
+                        e
+                    ),
+                );
+                tokio::select! {
+                    _ = monitor.shutdown.cancelled() => return,
+                    _ = tokio::time::sleep(COLLECTION_BACKFILL_RETRY_INTERVAL) => {}
+                }
+            }
+        });
+    }
 
-    // Handle the Ctrl+C signal
-    let ctrl_c = tokio::signal::ctrl_c();
+    // Keep the operator bond up to date for the metrics. Nothing reads the gauge without
+    // the HTTP server, and there is no bond to read without an operator.
+    if monitor.config.http_addr.is_some() && monitor.operator_address.is_some() {
+        let monitor_for_bond = monitor.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = monitor_for_bond.update_bond_amount().await {
+                    let network = monitor_for_bond.primary_network();
+                    monitor_for_bond.metrics.record_error(network, e.kind());
+                    monitor_for_bond.structured_log_error(
+                        &LogContext::network(network),
+                        &format!("Error reading the operator bond: {}", e),
+                    );
+                }
+                tokio::select! {
+                    _ = monitor_for_bond.shutdown.cancelled() => return,
+                    _ = tokio::time::sleep(BOND_POLL_INTERVAL) => {}
+                }
+            }
+        });
+    }
 
-    // This will run until a Ctrl+C signal is received.
-    tokio::select! {
-        _ = ctrl_c => {
-            println!("\nShutting down...");
-        }
-        _ = async {
-            // Sleep indefinitely to keep the program running
+    // Keep an eye on every RPC endpoint, failing over when the active one goes down
+    // or falls behind
+    for provider in monitor.providers.values() {
+        let provider = provider.clone();
+        let shutdown = monitor.shutdown.clone();
+        tokio::spawn(async move {
+            let client: &providers::FailoverClient = (*provider).as_ref();
             loop {
-                tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+                client.check_endpoints().await;
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = tokio::time::sleep(ENDPOINT_CHECK_INTERVAL) => {}
+                }
+            }
+        });
+    }
+
+    // Every network gets its own block channel and processing task, so a slow block on
+    // one network doesn't hold up the others.
+    let mut block_tasks = Vec::new();
+    for network_config in &monitor.config.networks {
+        let network = &network_config.name;
+        if !monitor.providers.contains_key(network) {
+            monitor.structured_log_error(
+                &LogContext::network(network),
+                &format!("Couldn't find the provider for the network {}.", network),
+            );
+            return Err(Error::Config(format!("Provider not found for network {}", network)));
+        }
+
+        // Start block monitoring for the network and pass both channels
+        let config = network_config.pipeline;
+        let (tx_blocks, rx_blocks) = mpsc::channel(config.queue_size);
+        monitor.network_subscribe(network, tx_logs.clone(), tx_blocks).await;
+
+        // Dedicated task for handling block messages of this network. Blocks are fetched
+        // by a pool of workers but committed in block order.
+        let monitor_for_block_task = monitor.clone();
+        let network = network.clone();
+        block_tasks.push(tokio::spawn(async move {
+            let monitor = &monitor_for_block_task;
+            let result = pipeline::run_ordered(
+                rx_blocks,
+                config.workers,
+                monitor.shutdown.clone(),
+                |block_job| monitor.fetch_block(block_job),
+                |processed| async move {
+                    // Nothing to commit for a block that was reorged out
+                    if let Some(processed) = processed {
+                        monitor.commit_block(processed).await;
+                    }
+                },
+            )
+            .await;
+            // The checkpoint stays before the block that failed, it's processed again
+            // on restart
+            if let Err(e) = result {
+                monitor.metrics.record_error(&network, e.kind());
+                monitor.structured_log_error(
+                    &LogContext::network(&network),
+                    &format!("Stopped processing blocks: {}", e),
+                );
             }
-        } => {}
+        }));
+    }
+
+    // Run until Ctrl+C or SIGTERM, then stop taking in new blocks and let the blocks
+    // that are already being processed finish.
+    let signal = shutdown::wait_for_signal().await;
+    monitor
+        .structured_log(&LogContext::default(), &format!("Received {}, shutting down...", signal));
+    monitor.shutdown.cancel();
+
+    let drained =
+        tokio::time::timeout(shutdown::SHUTDOWN_TIMEOUT, futures::future::join_all(block_tasks))
+            .await;
+    if drained.is_err() {
+        monitor.log(
+            LogLevel::Warn,
+            &LogContext::default(),
+            &format!("Gave up on in-flight blocks after {}s", shutdown::SHUTDOWN_TIMEOUT.as_secs()),
+        );
+    }
+
+    // Drop sales and deployments of blocks that aren't final yet are only held in
+    // memory, so resume before the first of those blocks
+    for (network, jobs) in monitor.block_jobs.lock().await.iter() {
+        let pending =
+            jobs.iter().find(|job| !job.drop_logs.is_empty() || !job.deployments.is_empty());
+        if let Some(pending) = pending {
+            monitor.checkpoints.set(network, pending.block.saturating_sub(1));
+        }
     }
 
+    // Blocks committed since the last periodic save would otherwise be processed again
+    monitor.checkpoints.save()?;
+    monitor.collections.save()?;
+    monitor.structured_log(&LogContext::default(), "Checkpoints saved, shutdown complete");
+
     Ok(())
 }
 
+// `inspect` prints the Holograph deployment of every configured network, `check`
+// compares the deployments, `deploy` deploys a holographable contract from a spec
+// and `drop` reads and administers drops. Without a command the monitor runs.
+async fn command(name: &str, args: &[String]) -> Result<()> {
+    dotenv().ok();
+    let config = Config::load()?;
+    match name {
+        "inspect" => inspect::run(&config).await,
+        "check" => consistency::run(&config).await,
+        "deploy" => deploy::run(&config, args).await,
+        "drop" => drops::run(&config, args).await,
+        _ => Err(Error::Config(format!("Unknown command: {}", name))),
+    }
+}
+
 #[tokio::main]
 async fn main() {
-    if let Err(e) = run().await {
-        println!("An error occurred: {:?}", e);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.split_first() {
+        None => run().await,
+        Some((name, args)) => command(name, args).await,
+    };
+    if let Err(e) = result {
+        eprintln!("An error occurred [{}]: {}", e.kind(), e);
+        std::process::exit(1);
     }
 }
 
@@ -719,3 +1684,181 @@ fn web_socket_error_codes() -> HashMap<i32, &'static str> {
     .into_iter()
     .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{
+        AvailableOperatorJobEvent, BaseEvent, FailedOperatorJobEvent, FinishedOperatorJobEvent,
+    };
+    use crate::types::TransactionType;
+
+    fn block_job(block: u64, hash: Option<H256>, parent_hash: Option<H256>) -> BlockJob {
+        BlockJob {
+            network: "test".to_string(),
+            block,
+            hash,
+            parent_hash,
+            finalized: false,
+            drop_logs: Vec::new(),
+            deployments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn block_jobs_match_only_the_recorded_block() {
+        let block: Block<Transaction> = Block {
+            number: Some(U64::from(7)),
+            hash: Some(H256::from_low_u64_be(7)),
+            parent_hash: H256::from_low_u64_be(6),
+            ..Default::default()
+        };
+        let (hash, parent) = (H256::from_low_u64_be(7), H256::from_low_u64_be(6));
+
+        assert!(block_job(7, Some(hash), Some(parent)).matches(&block));
+        assert!(block_job(7, None, None).matches(&block));
+        assert!(!block_job(8, None, None).matches(&block));
+        assert!(!block_job(7, Some(parent), Some(parent)).matches(&block));
+        assert!(!block_job(7, Some(hash), Some(H256::from_low_u64_be(106))).matches(&block));
+    }
+
+    fn base(event_type: EventType, contract: Address) -> BaseEvent {
+        BaseEvent { event_type, contract: format!("{:?}", contract), log_index: 0 }
+    }
+
+    fn interesting(
+        hash: u64,
+        from: Address,
+        bloom_id: &str,
+        events: Vec<DecodedEvent>,
+    ) -> InterestingTransaction {
+        InterestingTransaction {
+            bloom_id: bloom_id.to_string(),
+            transaction: Transaction {
+                hash: H256::from_low_u64_be(hash),
+                from,
+                ..Default::default()
+            },
+            receipt: Some(TransactionReceipt {
+                gas_used: Some(21_000.into()),
+                effective_gas_price: Some(2.into()),
+                ..Default::default()
+            }),
+            log: None,
+            all_logs: None,
+            events,
+            transaction_type: TransactionType::Unknown,
+        }
+    }
+
+    #[test]
+    fn transaction_metrics_count_each_transaction_once() {
+        let operator = Address::from_low_u64_be(0xaa);
+        let impostor = Address::from_low_u64_be(0xbb);
+        let wallet = Address::from_low_u64_be(1);
+        let other = Address::from_low_u64_be(2);
+        let job_events = || {
+            vec![
+                DecodedEvent::AvailableOperatorJob(AvailableOperatorJobEvent {
+                    base: base(EventType::AvailableOperatorJob, operator),
+                    job_hash: "0x01".to_string(),
+                    payload: "0x".to_string(),
+                }),
+                DecodedEvent::FinishedOperatorJob(FinishedOperatorJobEvent {
+                    base: base(EventType::FinishedOperatorJob, operator),
+                    job_hash: "0x01".to_string(),
+                    operator: format!("{:?}", wallet),
+                }),
+                // Same signature, another contract
+                DecodedEvent::AvailableOperatorJob(AvailableOperatorJobEvent {
+                    base: base(EventType::AvailableOperatorJob, impostor),
+                    job_hash: "0x02".to_string(),
+                    payload: "0x".to_string(),
+                }),
+            ]
+        };
+        let failed = vec![DecodedEvent::FailedOperatorJob(FailedOperatorJobEvent {
+            base: base(EventType::FailedOperatorJob, operator),
+            job_hash: "0x03".to_string(),
+        })];
+
+        let transactions = [
+            // Matched once per log
+            interesting(1, other, "operator", job_events()),
+            interesting(1, other, "operator", job_events()),
+            // Ours, although another filter matched first
+            interesting(2, wallet, "bridge", failed),
+            interesting(3, other, "bridge", Vec::new()),
+        ];
+        let (outcomes, gas_spent) = transaction_metrics(
+            &transactions,
+            Some(&format!("{:?}", operator)),
+            Some(&format!("{:?}", wallet)),
+        );
+
+        assert_eq!(outcomes, [JobOutcome::Seen, JobOutcome::Executed, JobOutcome::Failed]);
+        assert_eq!(gas_spent, U256::from(42_000));
+    }
+
+    #[test]
+    fn protocol_filters_match_before_the_wallet() {
+        let (tx_logs, _) = mpsc::channel(1);
+        let provider = Arc::new(
+            providers::connect("test", &["http://127.0.0.1:1".to_string()], tx_logs).unwrap(),
+        );
+        let (bridge, operator) = (Address::from_low_u64_be(0xbb), Address::from_low_u64_be(0xaa));
+        let contracts = HashMap::from([
+            ("bridge".to_string(), Contract::new(bridge, Abi::default(), provider.clone())),
+            ("operator".to_string(), Contract::new(operator, Abi::default(), provider)),
+        ]);
+        let networks = ["optimism".to_string(), "base".to_string()];
+        let wallet = Address::from_low_u64_be(1);
+        let mut filters = transaction_filters(&contracts, &networks);
+        filters.push(wallet_filter(format!("{:?}", wallet)));
+
+        let execute_job = ethers::abi::encode(&[ethers::abi::Token::Bytes(vec![1, 2, 3])]);
+        let execute_job =
+            [ethers::utils::id("executeJob(bytes)").as_slice(), &execute_job].concat();
+        let transaction = |hash: u64, to: Address, input: &[u8]| Transaction {
+            hash: H256::from_low_u64_be(hash),
+            from: wallet,
+            to: Some(to),
+            input: input.to_vec().into(),
+            ..Default::default()
+        };
+        let transactions = [
+            transaction(1, operator, &execute_job),
+            transaction(2, Address::from_low_u64_be(0xcc), &execute_job),
+            transaction(3, bridge, &[]),
+            transaction(4, Address::from_low_u64_be(0xcc), &[]),
+        ];
+        let available = EventType::AvailableOperatorJob.sig_hash().unwrap();
+        let logs = [Log {
+            address: operator,
+            topics: vec![available],
+            transaction_hash: Some(H256::from_low_u64_be(3)),
+            ..Default::default()
+        }];
+
+        let interesting = filter_transactions("base", &filters, &transactions, &logs);
+        let matched: Vec<_> = interesting
+            .iter()
+            .map(|interesting| {
+                (interesting.transaction.hash.to_low_u64_be(), interesting.bloom_id.as_str())
+            })
+            .collect();
+        assert_eq!(
+            matched,
+            [
+                (1, "operator"),
+                (2, "executeJob"),
+                (3, "bridge"),
+                (4, "wallet"),
+                (3, "AvailableOperatorJob")
+            ]
+        );
+        let mut bloom = Bloom::default();
+        bloom.accrue(ethers::abi::ethereum_types::BloomInput::Raw(available.as_bytes()));
+        assert!(check_bloom_logs(&bloom, "base", &filters));
+    }
+}
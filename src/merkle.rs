@@ -0,0 +1,131 @@
+use ethers::types::H256;
+use ethers::utils::keccak256;
+
+// A merkle tree hashed like OpenZeppelin's `MerkleProof`, which the drops use to check
+// presale proofs: pairs are sorted before hashing and an odd node is carried up as is.
+pub struct MerkleTree {
+    // Leaves first, the root last.
+    layers: Vec<Vec<H256>>,
+}
+
+fn hash_pair(a: H256, b: H256) -> H256 {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut packed = [0u8; 64];
+    packed[..32].copy_from_slice(first.as_bytes());
+    packed[32..].copy_from_slice(second.as_bytes());
+    H256::from(keccak256(packed))
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<H256>) -> Self {
+        let mut layers = vec![leaves];
+        while layers.last().is_some_and(|layer| layer.len() > 1) {
+            let next = layers
+                .last()
+                .into_iter()
+                .flat_map(|layer| layer.chunks(2))
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(*a, *b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+        MerkleTree { layers }
+    }
+
+    // Root of the tree, zero when there are no leaves.
+    pub fn root(&self) -> H256 {
+        self.layers.last().and_then(|layer| layer.first()).copied().unwrap_or_default()
+    }
+
+    // Proof for the leaf at `index`, the siblings from the leaf up to the root.
+    pub fn proof(&self, mut index: usize) -> Vec<H256> {
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+// `MerkleProof.verify`
+pub fn verify(proof: &[H256], root: H256, leaf: H256) -> bool {
+    proof.iter().fold(leaf, |hash, sibling| hash_pair(hash, *sibling)) == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u64) -> Vec<H256> {
+        (1..=count).map(|i| H256::from(keccak256(H256::from_low_u64_be(i)))).collect()
+    }
+
+    fn hash(hex: &str) -> H256 {
+        hex.parse().unwrap()
+    }
+
+    #[test]
+    fn empty_and_single_leaf() {
+        assert_eq!(MerkleTree::new(Vec::new()).root(), H256::zero());
+
+        let leaf = leaves(1)[0];
+        let tree = MerkleTree::new(vec![leaf]);
+        assert_eq!(tree.root(), leaf);
+        assert!(tree.proof(0).is_empty());
+        assert!(verify(&[], tree.root(), leaf));
+    }
+
+    #[test]
+    fn pairs_are_sorted() {
+        let leaves = leaves(2);
+        let forward = MerkleTree::new(leaves.clone());
+        let backward = MerkleTree::new(leaves.into_iter().rev().collect());
+        assert_eq!(forward.root(), backward.root());
+    }
+
+    // Root and proofs computed separately, folding the proof with a sorted keccak256 of
+    // each pair like OpenZeppelin's `MerkleProof.processProof`
+    #[test]
+    fn odd_leaf_count_matches_process_proof() {
+        let tree = MerkleTree::new(leaves(5));
+        assert_eq!(
+            tree.root(),
+            hash("0x9be4d908ee1467e12177bdda3d2712a12e7a2445350dccd4be9c218066530b19")
+        );
+        assert_eq!(
+            tree.proof(2),
+            vec![
+                hash("0x8a35acfbc15ff81a39ae7d344fd709f28e8600b4aa8c65c6b64bfe7fe36bd19b"),
+                hash("0x2a171b5bcd1449348c3e09a5424946b5e6d6f5471221941d585131d673952ee4"),
+                hash("0x036b6384b5eca791c62761152d0c79bb0604c104a5fb6f4eb0703f3154bb3db0"),
+            ]
+        );
+        // The last leaf is carried up until it has a sibling
+        assert_eq!(
+            tree.proof(4),
+            vec![hash("0x9cb86f87624f55e4956a62a87acdd72769cdb21f746c27d345ef90343a9b2316")]
+        );
+    }
+
+    #[test]
+    fn every_proof_verifies() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let tree = MerkleTree::new(leaves.clone());
+            for (index, leaf) in leaves.iter().enumerate() {
+                assert!(verify(&tree.proof(index), tree.root(), *leaf), "{}/{}", index, count);
+            }
+        }
+
+        let tree = MerkleTree::new(leaves(4));
+        let other = H256::from(keccak256(H256::from_low_u64_be(5)));
+        assert!(!verify(&tree.proof(0), tree.root(), other));
+        assert!(!verify(&tree.proof(1), tree.root(), leaves(4)[0]));
+    }
+}
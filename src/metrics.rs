@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ethers::types::U256;
+
+// What happened to an operator job, as far as this operator is concerned.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum JobOutcome {
+    // The job was made available on this network.
+    Seen,
+    // The job was executed by this operator.
+    Executed,
+    // The job was executed by another operator.
+    Skipped,
+    // The job was executed but failed.
+    Failed,
+}
+
+impl JobOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobOutcome::Seen => "seen",
+            JobOutcome::Executed => "executed",
+            JobOutcome::Skipped => "skipped",
+            JobOutcome::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Default)]
+struct RpcStats {
+    calls: u64,
+    errors: u64,
+    total_latency: Duration,
+}
+
+// Maps are ordered so the rendered output is stable between scrapes.
+#[derive(Default)]
+struct MetricsState {
+    head_block: BTreeMap<String, u64>,
+    processed_block: BTreeMap<String, u64>,
+    rpc: BTreeMap<(String, String), RpcStats>,
+    errors: BTreeMap<(String, String), u64>,
+    jobs: BTreeMap<(String, JobOutcome), u64>,
+    gas_spent: BTreeMap<String, u128>,
+    bond_amount: BTreeMap<String, u128>,
+}
+
+// Counters and gauges exposed in the Prometheus text format on `/metrics`.
+#[derive(Default)]
+pub struct Metrics {
+    state: Mutex<MetricsState>,
+}
+
+impl Metrics {
+    fn state(&self) -> std::sync::MutexGuard<'_, MetricsState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_head_block(&self, network: &str, block: u64) {
+        self.state().head_block.insert(network.to_string(), block);
+    }
+
+    pub fn set_processed_block(&self, network: &str, block: u64) {
+        self.state().processed_block.insert(network.to_string(), block);
+    }
+
+    // Await an RPC call, recording how long it took and whether it failed.
+    pub async fn time_rpc<Fut, T, E>(
+        &self,
+        network: &str,
+        method: &str,
+        call: Fut,
+    ) -> std::result::Result<T, E>
+    where
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        let started = Instant::now();
+        let result = call.await;
+        let elapsed = started.elapsed();
+
+        let mut state = self.state();
+        let stats = state.rpc.entry((network.to_string(), method.to_string())).or_default();
+        stats.calls += 1;
+        stats.total_latency += elapsed;
+        if result.is_err() {
+            stats.errors += 1;
+        }
+        result
+    }
+
+    // Count an error of the given kind, see `Error::kind`.
+    pub fn record_error(&self, network: &str, kind: &str) {
+        *self.state().errors.entry((network.to_string(), kind.to_string())).or_default() += 1;
+    }
+
+    pub fn record_job(&self, network: &str, outcome: JobOutcome) {
+        *self.state().jobs.entry((network.to_string(), outcome)).or_default() += 1;
+    }
+
+    pub fn add_gas_spent(&self, network: &str, wei: U256) {
+        let wei = u128::try_from(wei).unwrap_or(u128::MAX);
+        let mut state = self.state();
+        let total = state.gas_spent.entry(network.to_string()).or_default();
+        *total = total.saturating_add(wei);
+    }
+
+    pub fn set_bond_amount(&self, network: &str, wei: U256) {
+        let wei = u128::try_from(wei).unwrap_or(u128::MAX);
+        self.state().bond_amount.insert(network.to_string(), wei);
+    }
+
+    // Render every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let state = self.state();
+        let mut out = String::new();
+
+        header(&mut out, "holograph_head_block", "gauge", "Latest block seen on the network.");
+        for (network, block) in &state.head_block {
+            let _ = writeln!(out, "holograph_head_block{{network=\"{}\"}} {}", network, block);
+        }
+
+        header(&mut out, "holograph_processed_block", "gauge", "Latest block fully processed.");
+        for (network, block) in &state.processed_block {
+            let _ = writeln!(out, "holograph_processed_block{{network=\"{}\"}} {}", network, block);
+        }
+
+        header(&mut out, "holograph_block_lag", "gauge", "Blocks seen but not processed yet.");
+        for (network, head) in &state.head_block {
+            let processed = state.processed_block.get(network).copied().unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "holograph_block_lag{{network=\"{}\"}} {}",
+                network,
+                head.saturating_sub(processed)
+            );
+        }
+
+        header(&mut out, "holograph_rpc_calls_total", "counter", "RPC calls made.");
+        for ((network, method), stats) in &state.rpc {
+            let _ = writeln!(
+                out,
+                "holograph_rpc_calls_total{{network=\"{}\",method=\"{}\"}} {}",
+                network, method, stats.calls
+            );
+        }
+
+        header(&mut out, "holograph_rpc_errors_total", "counter", "RPC calls that failed.");
+        for ((network, method), stats) in &state.rpc {
+            let _ = writeln!(
+                out,
+                "holograph_rpc_errors_total{{network=\"{}\",method=\"{}\"}} {}",
+                network, method, stats.errors
+            );
+        }
+
+        header(
+            &mut out,
+            "holograph_rpc_latency_seconds",
+            "summary",
+            "Time spent waiting on RPC calls.",
+        );
+        for ((network, method), stats) in &state.rpc {
+            let _ = writeln!(
+                out,
+                "holograph_rpc_latency_seconds_sum{{network=\"{}\",method=\"{}\"}} {}",
+                network,
+                method,
+                stats.total_latency.as_secs_f64()
+            );
+            let _ = writeln!(
+                out,
+                "holograph_rpc_latency_seconds_count{{network=\"{}\",method=\"{}\"}} {}",
+                network, method, stats.calls
+            );
+        }
+
+        header(&mut out, "holograph_errors_total", "counter", "Errors by type.");
+        for ((network, kind), count) in &state.errors {
+            let _ = writeln!(
+                out,
+                "holograph_errors_total{{network=\"{}\",kind=\"{}\"}} {}",
+                network, kind, count
+            );
+        }
+
+        header(&mut out, "holograph_operator_jobs_total", "counter", "Operator jobs by outcome.");
+        for ((network, outcome), count) in &state.jobs {
+            let _ = writeln!(
+                out,
+                "holograph_operator_jobs_total{{network=\"{}\",outcome=\"{}\"}} {}",
+                network,
+                outcome.as_str(),
+                count
+            );
+        }
+
+        header(&mut out, "holograph_gas_spent_wei_total", "counter", "Gas spent by the operator.");
+        for (network, wei) in &state.gas_spent {
+            let _ =
+                writeln!(out, "holograph_gas_spent_wei_total{{network=\"{}\"}} {}", network, wei);
+        }
+
+        header(&mut out, "holograph_bond_amount_wei", "gauge", "Amount bonded by the operator.");
+        for (network, wei) in &state.bond_amount {
+            let _ = writeln!(out, "holograph_bond_amount_wei{{network=\"{}\"}} {}", network, wei);
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
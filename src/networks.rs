@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+// Chain ID namespaces understood by `HolographInterfaces.getChainId`, numbered as in
+// the `ChainIdType` enum of the contracts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChainIdType {
+    Evm = 1,
+    Holograph = 2,
+    LayerZero = 3,
+}
+
+// A network Holograph is deployed on. Networks are referred to by `name` everywhere
+// else, e.g. in the configuration and in logs.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Network {
+    pub name: &'static str,
+    pub chain_id: u64,
+    pub holograph_id: u32,
+    pub layer_zero_id: u16,
+    pub native_token: &'static str,
+    pub block_time: Duration,
+    // Blocks to wait before a block counts as final, see `confirmations::ConfirmationPolicy`.
+    pub confirmations: u64,
+    pub testnet: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn network(
+    name: &'static str,
+    chain_id: u64,
+    holograph_id: u32,
+    layer_zero_id: u16,
+    native_token: &'static str,
+    block_time_ms: u64,
+    confirmations: u64,
+    testnet: bool,
+) -> Network {
+    Network {
+        name,
+        chain_id,
+        holograph_id,
+        layer_zero_id,
+        native_token,
+        block_time: Duration::from_millis(block_time_ms),
+        confirmations,
+        testnet,
+    }
+}
+
+// Every network we know about. The Holograph and LayerZero IDs are checked against
+// `HolographInterfaces.getChainId` at startup, see `verify_chain_ids` in main.
+pub const NETWORKS: &[Network] = &[
+    network("localhost", 1338, 4294967295, 65535, "ETH", 1_000, 5, true),
+    network("localhost2", 1339, 4294967294, 65534, "ETH", 1_000, 5, true),
+    network("ethereum", 1, 4000000001, 101, "ETH", 12_000, 12, false),
+    network("ethereumTestnetGoerli", 5, 4000000011, 10121, "ETH", 12_000, 12, true),
+    network("binanceSmartChain", 56, 4000000002, 102, "BNB", 3_000, 15, false),
+    network("binanceSmartChainTestnet", 97, 4000000012, 10102, "BNB", 3_000, 15, true),
+    network("avalanche", 43114, 4000000003, 106, "AVAX", 2_000, 2, false),
+    network("avalancheTestnet", 43113, 4000000013, 10106, "AVAX", 2_000, 2, true),
+    network("polygon", 137, 4000000004, 109, "MATIC", 2_000, 64, false),
+    network("polygonTestnet", 80001, 4000000014, 10109, "MATIC", 2_000, 64, true),
+    network("arbitrumOne", 42161, 4000000005, 110, "ETH", 250, 5, false),
+    network("arbitrumTestnetGoerli", 421613, 4000000015, 10143, "ETH", 250, 5, true),
+    network("optimism", 10, 4000000006, 111, "ETH", 2_000, 5, false),
+    network("optimismTestnetGoerli", 420, 4000000016, 10132, "ETH", 2_000, 5, true),
+    network("base", 8453, 4000000008, 184, "ETH", 2_000, 5, false),
+    network("baseTestnetGoerli", 84531, 4000000018, 10160, "ETH", 2_000, 5, true),
+    network("mantle", 5000, 4000000009, 181, "MNT", 2_000, 5, false),
+    network("mantleTestnet", 5001, 4000000019, 10181, "MNT", 2_000, 5, true),
+    network("zora", 7777777, 4000000010, 195, "ETH", 2_000, 5, false),
+    network("zoraTestnetGoerli", 999, 4000000020, 10195, "ETH", 2_000, 5, true),
+];
+
+pub fn get(name: &str) -> Option<&'static Network> {
+    NETWORKS.iter().find(|network| network.name == name)
+}
+
+pub fn by_chain_id(chain_id: u64) -> Option<&'static Network> {
+    NETWORKS.iter().find(|network| network.chain_id == chain_id)
+}
@@ -0,0 +1,225 @@
+use std::future::Future;
+
+use futures::stream::{self, StreamExt};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+pub const DEFAULT_BLOCK_WORKERS: usize = 4;
+pub const DEFAULT_BLOCK_QUEUE_SIZE: usize = 256;
+
+// Sizing of the block processing pipeline of a network.
+#[derive(Debug, Clone, Copy)]
+pub struct PipelineConfig {
+    // Blocks fetched at the same time.
+    pub workers: usize,
+    // Block jobs that can be queued before the subscription has to wait.
+    pub queue_size: usize,
+}
+
+// Run `fetch` for up to `workers` jobs at the same time, but hand the results to
+// `commit` strictly in the order the jobs were received. Fetching carries on while a
+// result is being committed.
+//
+// Returns once the job channel is closed, or `shutdown` is cancelled, and every job
+// that was already being fetched has been committed. Jobs still queued on shutdown
+// are left alone. A failed fetch stops the pipeline with its error: nothing after it
+// is committed, so whatever `commit` records never skips over a job.
+pub async fn run_ordered<J, R, E, F, FFut, C, CFut>(
+    rx_jobs: mpsc::Receiver<J>,
+    workers: usize,
+    shutdown: CancellationToken,
+    fetch: F,
+    mut commit: C,
+) -> Result<(), E>
+where
+    F: FnMut(J) -> FFut,
+    FFut: Future<Output = Result<R, E>>,
+    C: FnMut(R) -> CFut,
+    CFut: Future<Output = ()>,
+{
+    let workers = workers.max(1);
+    let jobs = stream::unfold((rx_jobs, shutdown), |(mut rx_jobs, shutdown)| async move {
+        tokio::select! {
+            biased;
+            _ = shutdown.cancelled() => None,
+            job = rx_jobs.recv() => job.map(|job| (job, (rx_jobs, shutdown))),
+        }
+    });
+    let results = jobs.map(fetch).buffered(workers);
+    let (tx_results, mut rx_results) = mpsc::channel(workers);
+
+    // Both halves run in this task: fetched results queue up in the channel while a
+    // commit is awaited, and fetching stops as soon as committing does.
+    let fetching = async move {
+        futures::pin_mut!(results);
+        loop {
+            let result = tokio::select! {
+                _ = tx_results.closed() => return,
+                result = results.next() => result,
+            };
+            match result {
+                Some(result) => {
+                    if tx_results.send(result).await.is_err() {
+                        return;
+                    }
+                }
+                None => return,
+            }
+        }
+    };
+    let committing = async move {
+        while let Some(result) = rx_results.recv().await {
+            commit(result?).await;
+        }
+        Ok(())
+    };
+
+    let ((), result) = tokio::join!(fetching, committing);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tokio::sync::Notify;
+    use tokio::time::{sleep, timeout};
+
+    async fn send_jobs(jobs: impl IntoIterator<Item = u64>) -> mpsc::Receiver<u64> {
+        let (tx_jobs, rx_jobs) = mpsc::channel(16);
+        for job in jobs {
+            tx_jobs.send(job).await.unwrap();
+        }
+        rx_jobs
+    }
+
+    #[tokio::test]
+    async fn results_are_committed_in_job_order() {
+        let committed = Mutex::new(Vec::new());
+        let result: Result<(), ()> = run_ordered(
+            send_jobs(0..8).await,
+            4,
+            CancellationToken::new(),
+            // Later jobs finish first
+            |job| async move {
+                sleep(Duration::from_millis(40 - 5 * job)).await;
+                Ok(job)
+            },
+            |job| {
+                committed.lock().unwrap().push(job);
+                async {}
+            },
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(*committed.lock().unwrap(), (0..8).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn failed_fetch_stops_before_anything_after_it_is_committed() {
+        let (tx_jobs, rx_jobs) = mpsc::channel(16);
+        for job in 0..6 {
+            tx_jobs.send(job).await.unwrap();
+        }
+        let committed = Mutex::new(Vec::new());
+        let pipeline = run_ordered(
+            rx_jobs,
+            3,
+            CancellationToken::new(),
+            |job| async move {
+                // The jobs after the failed one are fetched first
+                sleep(Duration::from_millis(if job == 2 { 30 } else { 1 })).await;
+                if job == 2 {
+                    Err(format!("block {} could not be fetched", job))
+                } else {
+                    Ok(job)
+                }
+            },
+            |job| {
+                committed.lock().unwrap().push(job);
+                async {}
+            },
+        );
+
+        // Returns although the job channel is still open
+        let result = timeout(Duration::from_secs(5), pipeline).await.expect("pipeline should stop");
+        assert_eq!(result, Err("block 2 could not be fetched".to_string()));
+        assert_eq!(*committed.lock().unwrap(), [0, 1]);
+        drop(tx_jobs);
+    }
+
+    #[tokio::test]
+    async fn fetching_continues_while_a_commit_is_awaited() {
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let fetched_more = Arc::new(Notify::new());
+        let result: Result<(), ()> = run_ordered(
+            send_jobs(0..6).await,
+            2,
+            CancellationToken::new(),
+            |job| {
+                let (fetched, fetched_more) = (fetched.clone(), fetched_more.clone());
+                async move {
+                    fetched.fetch_add(1, Ordering::SeqCst);
+                    fetched_more.notify_waiters();
+                    Ok(job)
+                }
+            },
+            |job| {
+                let (fetched, fetched_more) = (fetched.clone(), fetched_more.clone());
+                async move {
+                    // The first commit only finishes once later jobs were fetched
+                    while job == 0 && fetched.load(Ordering::SeqCst) < 4 {
+                        let notified = fetched_more.notified();
+                        if fetched.load(Ordering::SeqCst) >= 4 {
+                            break;
+                        }
+                        timeout(Duration::from_secs(5), notified)
+                            .await
+                            .expect("fetches should overlap the commit");
+                    }
+                }
+            },
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(fetched.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn shutdown_commits_what_is_being_fetched() {
+        let (tx_jobs, rx_jobs) = mpsc::channel(16);
+        let shutdown = CancellationToken::new();
+        let committed = Mutex::new(Vec::new());
+        tx_jobs.send(0).await.unwrap();
+
+        let pipeline = run_ordered(
+            rx_jobs,
+            2,
+            shutdown.clone(),
+            |job| {
+                let shutdown = shutdown.clone();
+                async move {
+                    // Shut down while the job is being fetched
+                    shutdown.cancel();
+                    sleep(Duration::from_millis(10)).await;
+                    Ok::<_, ()>(job)
+                }
+            },
+            |job| {
+                committed.lock().unwrap().push(job);
+                async {}
+            },
+        );
+
+        let result = timeout(Duration::from_secs(5), pipeline).await.expect("pipeline should stop");
+        assert!(result.is_ok());
+        assert_eq!(*committed.lock().unwrap(), [0]);
+        drop(tx_jobs);
+    }
+}
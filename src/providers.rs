@@ -0,0 +1,420 @@
+use crate::error::{Error, Result};
+use crate::health::ProviderStatus;
+use crate::logging::{LogContext, LogLevel};
+use crate::networks;
+use crate::LogMessage;
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::providers::{
+    is_local_endpoint, HttpClientError, JsonRpcClient, JsonRpcError, RpcError,
+    DEFAULT_LOCAL_POLL_INTERVAL,
+};
+use futures::future::join_all;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+// Provider used for every network, failing over between the configured endpoints.
+pub type RpcProvider = Provider<FailoverClient>;
+
+// An endpoint this many blocks behind the best endpoint of its network is failed over from
+pub const MAX_ENDPOINT_LAG: u64 = 5;
+
+// How often every endpoint is checked for its status, latency and block height
+pub const ENDPOINT_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Create the provider of a network, polling as often as the chain produces blocks.
+pub fn connect(
+    network: &str,
+    urls: &[String],
+    tx_logs: mpsc::Sender<LogMessage>,
+) -> Result<RpcProvider> {
+    let local = urls.iter().all(|url| is_local_endpoint(url));
+    let mut provider = Provider::new(FailoverClient::new(network, urls, tx_logs)?);
+
+    if local {
+        provider.set_interval(DEFAULT_LOCAL_POLL_INTERVAL);
+    } else if let Some(network) = networks::get(network) {
+        provider.set_interval(network.block_time / 2);
+    }
+    Ok(provider)
+}
+
+#[derive(Debug)]
+struct EndpointState {
+    // NotConfigured until the endpoint has been contacted for the first time
+    status: ProviderStatus,
+    latency: Option<Duration>,
+    block: Option<u64>,
+    // Set once the endpoint returned the chain ID of the network. Requests only go to
+    // verified endpoints, so a wrong URL can't make us sign transactions on another chain.
+    chain_verified: bool,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    // Only the host is logged, URLs often contain API keys
+    label: String,
+    client: Http,
+    state: Mutex<EndpointState>,
+}
+
+impl Endpoint {
+    fn state(&self) -> std::sync::MutexGuard<'_, EndpointState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// JSON-RPC client sending every request to the active endpoint of a network, and
+// moving on to the next healthy endpoint when it can't be reached. Error responses
+// from a node (e.g. reverts) are returned as is, the endpoint itself is fine.
+//
+// Nothing is kept on the endpoints between requests: the block subscription polls
+// the block number rather than using filters, which only exist on the node that
+// created them, so it carries on with the next endpoint after a failover.
+pub struct FailoverClient {
+    network: String,
+    // Chain ID the endpoints must serve, None for networks outside the networks table
+    chain_id: Option<u64>,
+    endpoints: Vec<Endpoint>,
+    active: AtomicUsize,
+    tx_logs: mpsc::Sender<LogMessage>,
+}
+
+impl fmt::Debug for FailoverClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FailoverClient")
+            .field("network", &self.network)
+            .field("endpoints", &self.endpoints)
+            .field("active", &self.active)
+            .finish()
+    }
+}
+
+impl FailoverClient {
+    pub fn new(network: &str, urls: &[String], tx_logs: mpsc::Sender<LogMessage>) -> Result<Self> {
+        let chain_id = networks::get(network).map(|network| network.chain_id);
+        let endpoints = urls
+            .iter()
+            .enumerate()
+            .map(|(index, url)| {
+                let client: Http = url.parse().map_err(|e| {
+                    Error::Config(format!("Invalid RPC URL #{} for {}: {}", index + 1, network, e))
+                })?;
+                let host = client.url().host_str().unwrap_or("unknown").to_string();
+                Ok(Endpoint {
+                    label: format!("#{} ({})", index + 1, host),
+                    client,
+                    state: Mutex::new(EndpointState {
+                        status: ProviderStatus::NotConfigured,
+                        latency: None,
+                        block: None,
+                        chain_verified: chain_id.is_none(),
+                    }),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(FailoverClient {
+            network: network.to_string(),
+            chain_id,
+            endpoints,
+            active: AtomicUsize::new(0),
+            tx_logs,
+        })
+    }
+
+    fn log(&self, level: LogLevel, msg: String) {
+        let context = LogContext::network(&self.network).tag(Some("rpc"));
+        let _ = self.tx_logs.try_send(LogMessage { level, context, msg });
+    }
+
+    // Record the outcome of a call to an endpoint, logging status changes.
+    fn update(&self, index: usize, status: ProviderStatus, latency: Option<Duration>) {
+        let endpoint = &self.endpoints[index];
+        let previous = {
+            let mut state = endpoint.state();
+            if latency.is_some() {
+                state.latency = latency;
+            }
+            std::mem::replace(&mut state.status, status)
+        };
+
+        if previous != status {
+            let level = match status {
+                ProviderStatus::Connected => LogLevel::Info,
+                _ => LogLevel::Warn,
+            };
+            self.log(
+                level,
+                format!(
+                    "RPC endpoint {} is {} (was {})",
+                    endpoint.label,
+                    status.as_str(),
+                    previous.as_str()
+                ),
+            );
+        }
+    }
+
+    fn switch_to(&self, index: usize, reason: &str) {
+        let previous = self.active.swap(index, Ordering::SeqCst);
+        if previous != index {
+            self.log(
+                LogLevel::Warn,
+                format!(
+                    "Failing over from RPC endpoint {} to {}: {}",
+                    self.endpoints[previous].label, self.endpoints[index].label, reason
+                ),
+            );
+        }
+    }
+
+    // The active endpoint first, then the others, leaving disconnected endpoints for last.
+    // Endpoints that haven't been verified to serve our chain are left out.
+    fn failover_order(&self) -> Vec<usize> {
+        let active = self.active.load(Ordering::SeqCst);
+        let count = self.endpoints.len();
+        let mut order: Vec<usize> = (0..count)
+            .map(|offset| (active + offset) % count)
+            .filter(|index| self.endpoints[*index].state().chain_verified)
+            .collect();
+        if let Some(rest) = order.get_mut(1..) {
+            rest.sort_by_key(|index| {
+                self.endpoints[*index].state().status == ProviderStatus::Disconnected
+            });
+        }
+        order
+    }
+
+    // Ask an endpoint for its chain ID, marking it verified when it serves `expected`.
+    // Returns a description of the mismatch when it serves another chain.
+    async fn check_chain_id(
+        &self,
+        endpoint: &Endpoint,
+        expected: u64,
+    ) -> std::result::Result<Option<String>, HttpClientError> {
+        let chain_id: U256 = JsonRpcClient::request(&endpoint.client, "eth_chainId", ()).await?;
+        let verified = chain_id == U256::from(expected);
+        endpoint.state().chain_verified = verified;
+        if verified {
+            return Ok(None);
+        }
+        let serves = match networks::by_chain_id(chain_id.low_u64()) {
+            Some(network) => format!("chain {} ({})", chain_id, network.name),
+            None => format!("chain {}", chain_id),
+        };
+        Ok(Some(format!(
+            "RPC endpoint {} of {} serves {}, expected chain {}",
+            endpoint.label, self.network, serves, expected
+        )))
+    }
+
+    // Check that every endpoint serves the chain of the network, so a wrong URL can't
+    // make us sign transactions on another chain. Endpoints that can't be reached are
+    // only warned about, the others must all match.
+    pub async fn verify_chain_id(&self, expected: u64) -> Vec<String> {
+        let checks = self.endpoints.iter().map(|endpoint| self.check_chain_id(endpoint, expected));
+        let results = join_all(checks).await;
+
+        let mut mismatches = Vec::new();
+        for (endpoint, result) in self.endpoints.iter().zip(results) {
+            match result {
+                Ok(None) => {}
+                Ok(Some(mismatch)) => mismatches.push(mismatch),
+                Err(e) => self.log(
+                    LogLevel::Warn,
+                    format!(
+                        "Couldn't verify the chain ID of RPC endpoint {}: {}",
+                        endpoint.label, e
+                    ),
+                ),
+            }
+        }
+        mismatches
+    }
+
+    // Check the status, latency and block height of every endpoint, and fail over when
+    // the active endpoint is down or has fallen behind the others. Endpoints that weren't
+    // verified to serve our chain yet are asked for their chain ID first.
+    pub async fn check_endpoints(&self) {
+        let checks = self.endpoints.iter().map(|endpoint| async move {
+            let unverified = !endpoint.state().chain_verified;
+            if let (true, Some(expected)) = (unverified, self.chain_id) {
+                match self.check_chain_id(endpoint, expected).await {
+                    Ok(None) => {}
+                    Ok(Some(mismatch)) => {
+                        self.log(LogLevel::Error, mismatch);
+                        return (None, Duration::ZERO);
+                    }
+                    Err(_) => return (None, Duration::ZERO),
+                }
+            }
+            let started = Instant::now();
+            let result =
+                JsonRpcClient::request::<_, U64>(&endpoint.client, "eth_blockNumber", ()).await;
+            (result.ok().map(|block| block.as_u64()), started.elapsed())
+        });
+        let results = join_all(checks).await;
+
+        for (index, (block, latency)) in results.into_iter().enumerate() {
+            match block {
+                Some(block) => {
+                    self.endpoints[index].state().block = Some(block);
+                    self.update(index, ProviderStatus::Connected, Some(latency));
+                }
+                None => self.update(index, ProviderStatus::Disconnected, None),
+            }
+        }
+
+        // Healthy endpoints with their block height and latency
+        let healthy: Vec<(usize, u64, Duration)> = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .filter_map(|(index, endpoint)| {
+                let state = endpoint.state();
+                match (state.status, state.block) {
+                    (ProviderStatus::Connected, Some(block)) if state.chain_verified => {
+                        Some((index, block, state.latency.unwrap_or_default()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        let Some(best_block) = healthy.iter().map(|(_, block, _)| *block).max() else {
+            return;
+        };
+
+        let active = self.active.load(Ordering::SeqCst);
+        let reason = match healthy.iter().find(|(index, _, _)| *index == active) {
+            None => "endpoint is down".to_string(),
+            Some((_, block, _)) if block + MAX_ENDPOINT_LAG < best_block => {
+                format!("endpoint is {} blocks behind", best_block - block)
+            }
+            Some(_) => return,
+        };
+
+        // Prefer the fastest endpoint among those that are caught up
+        let candidate = healthy
+            .iter()
+            .filter(|(_, block, _)| block + MAX_ENDPOINT_LAG >= best_block)
+            .min_by_key(|(_, _, latency)| *latency);
+        if let Some((index, _, _)) = candidate {
+            self.switch_to(*index, &reason);
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FailoverClient {
+    type Error = FailoverError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> std::result::Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params).map_err(FailoverError::Serde)?;
+        let active = self.active.load(Ordering::SeqCst);
+
+        // Commands use the client without verifying it first, so verify the endpoints on
+        // the first request
+        let mut order = self.failover_order();
+        if let (true, Some(expected)) = (order.is_empty(), self.chain_id) {
+            for mismatch in self.verify_chain_id(expected).await {
+                self.log(LogLevel::Error, mismatch);
+            }
+            order = self.failover_order();
+        }
+
+        let mut last_error = None;
+        for index in order {
+            let started = Instant::now();
+            let client = &self.endpoints[index].client;
+            match JsonRpcClient::request(client, method, &params).await {
+                Ok(result) => {
+                    self.update(index, ProviderStatus::Connected, Some(started.elapsed()));
+                    if index != active {
+                        self.switch_to(index, "endpoint could not be reached");
+                    }
+                    return Ok(result);
+                }
+                Err(e @ HttpClientError::JsonRpcError(_)) => {
+                    self.update(index, ProviderStatus::Connected, Some(started.elapsed()));
+                    return Err(FailoverError::Http(e));
+                }
+                Err(e) => {
+                    self.update(index, ProviderStatus::Disconnected, None);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.map(FailoverError::Http).unwrap_or(FailoverError::NoVerifiedEndpoints))
+    }
+}
+
+#[derive(Debug)]
+pub enum FailoverError {
+    // The error of the last endpoint that was tried.
+    Http(HttpClientError),
+    // The request parameters could not be serialized.
+    Serde(serde_json::Error),
+    // No endpoint could be verified to serve the chain of the network.
+    NoVerifiedEndpoints,
+}
+
+impl fmt::Display for FailoverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailoverError::Http(e) => write!(f, "{}", e),
+            FailoverError::Serde(e) => write!(f, "Failed to serialize request: {}", e),
+            FailoverError::NoVerifiedEndpoints => {
+                write!(f, "No RPC endpoint serving the chain of the network could be reached")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FailoverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FailoverError::Http(e) => Some(e),
+            FailoverError::Serde(e) => Some(e),
+            FailoverError::NoVerifiedEndpoints => None,
+        }
+    }
+}
+
+impl RpcError for FailoverError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            FailoverError::Http(e) => e.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            FailoverError::Http(e) => e.as_serde_error(),
+            FailoverError::Serde(e) => Some(e),
+            FailoverError::NoVerifiedEndpoints => None,
+        }
+    }
+}
+
+impl From<FailoverError> for ProviderError {
+    fn from(e: FailoverError) -> Self {
+        match e {
+            FailoverError::Http(e) => e.into(),
+            e => ProviderError::JsonRpcClientError(Box::new(e)),
+        }
+    }
+}
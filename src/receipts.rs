@@ -0,0 +1,123 @@
+use crate::error::Result;
+use crate::events::{decode_log, DecodedEvent};
+use crate::metrics::Metrics;
+use crate::providers::RpcProvider;
+use crate::types::{InterestingTransaction, TransactionType};
+
+use std::collections::{HashMap, VecDeque};
+
+use ethers::prelude::*;
+use ethers::types::{TransactionReceipt, H256};
+use futures::future::join_all;
+
+// Number of receipts requested from the provider at the same time
+pub const RECEIPT_BATCH_SIZE: usize = 10;
+
+// Number of receipts kept around for blocks that get processed more than once
+pub const RECEIPT_CACHE_SIZE: usize = 1024;
+
+// Bounded cache of transaction receipts, evicting the oldest entry first.
+pub struct ReceiptCache {
+    receipts: HashMap<H256, TransactionReceipt>,
+    order: VecDeque<H256>,
+    capacity: usize,
+}
+
+impl ReceiptCache {
+    pub fn new(capacity: usize) -> Self {
+        ReceiptCache { receipts: HashMap::new(), order: VecDeque::new(), capacity }
+    }
+
+    // Receipts are only valid for the block they were mined in, a reorg can move the
+    // same transaction into a different block.
+    pub fn get(&self, transaction_hash: &H256, block_hash: H256) -> Option<&TransactionReceipt> {
+        self.receipts.get(transaction_hash).filter(|receipt| receipt.block_hash == Some(block_hash))
+    }
+
+    pub fn insert(&mut self, receipt: TransactionReceipt) {
+        let transaction_hash = receipt.transaction_hash;
+        if self.receipts.insert(transaction_hash, receipt).is_none() {
+            self.order.push_back(transaction_hash);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.receipts.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl Default for ReceiptCache {
+    fn default() -> Self {
+        Self::new(RECEIPT_CACHE_SIZE)
+    }
+}
+
+// Classify a transaction by what it did, looking at the decoded events first and
+// falling back to whether it created a contract.
+pub fn classify(receipt: &TransactionReceipt, events: &[DecodedEvent]) -> TransactionType {
+    let deployed =
+        events.iter().any(|event| matches!(event, DecodedEvent::BridgeableContractDeployed(_)));
+    if deployed || receipt.contract_address.is_some() {
+        return TransactionType::Deploy;
+    }
+    if events.iter().any(|event| matches!(event, DecodedEvent::TransferERC721(_))) {
+        return TransactionType::Erc721;
+    }
+    if events.iter().any(|event| matches!(event, DecodedEvent::TransferERC20(_))) {
+        return TransactionType::Erc20;
+    }
+    TransactionType::Unknown
+}
+
+// Fetch the receipt of every interesting transaction in a block and attach it,
+// together with all of its logs and the events decoded from them.
+pub async fn enrich_transactions(
+    provider: &RpcProvider,
+    cache: &tokio::sync::Mutex<ReceiptCache>,
+    metrics: &Metrics,
+    network: &str,
+    block_hash: H256,
+    interesting_transactions: &mut [InterestingTransaction],
+) -> Result<()> {
+    // Several interesting transactions can point at the same transaction hash
+    let mut missing: Vec<H256> = Vec::new();
+    {
+        let cache = cache.lock().await;
+        for interesting in interesting_transactions.iter() {
+            let hash = interesting.transaction.hash;
+            if cache.get(&hash, block_hash).is_none() && !missing.contains(&hash) {
+                missing.push(hash);
+            }
+        }
+    }
+
+    for batch in missing.chunks(RECEIPT_BATCH_SIZE) {
+        let receipts = join_all(batch.iter().map(|hash| {
+            metrics.time_rpc(
+                network,
+                "eth_getTransactionReceipt",
+                provider.get_transaction_receipt(*hash),
+            )
+        }))
+        .await;
+        let mut cache = cache.lock().await;
+        for receipt in receipts {
+            if let Some(receipt) = receipt? {
+                cache.insert(receipt);
+            }
+        }
+    }
+
+    let cache = cache.lock().await;
+    for interesting in interesting_transactions.iter_mut() {
+        if let Some(receipt) = cache.get(&interesting.transaction.hash, block_hash) {
+            interesting.events = receipt.logs.iter().filter_map(decode_log).collect();
+            interesting.transaction_type = classify(receipt, &interesting.events);
+            interesting.all_logs = Some(receipt.logs.clone());
+            interesting.receipt = Some(receipt.clone());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use crate::error::Result;
+use crate::providers::RpcProvider;
+
+use std::collections::VecDeque;
+
+use ethers::prelude::*;
+use ethers::types::H256;
+
+// Number of recent block hashes kept per network. Reorgs deeper than this
+// can't be resolved from history alone and are reported as such.
+pub const BLOCK_HISTORY_WINDOW: usize = 128;
+
+// Rolling window of the most recent (block number, block hash) pairs seen on a network.
+pub struct BlockHistory {
+    blocks: VecDeque<(u64, H256)>,
+    capacity: usize,
+}
+
+impl BlockHistory {
+    pub fn new(capacity: usize) -> Self {
+        BlockHistory { blocks: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn hash_at(&self, number: u64) -> Option<H256> {
+        self.blocks.iter().rev().find(|(n, _)| *n == number).map(|(_, hash)| *hash)
+    }
+
+    // Record a block, dropping the oldest entry once the window is full.
+    // Entries at or above `number` are replaced since they belong to another branch.
+    pub fn push(&mut self, number: u64, hash: H256) {
+        self.truncate_after(number.saturating_sub(1));
+        if self.blocks.len() == self.capacity {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back((number, hash));
+    }
+
+    // Remove and return every entry above `number`, oldest first.
+    pub fn truncate_after(&mut self, number: u64) -> Vec<(u64, H256)> {
+        let keep = self.blocks.iter().take_while(|(n, _)| *n <= number).count();
+        self.blocks.drain(keep..).collect()
+    }
+}
+
+impl Default for BlockHistory {
+    fn default() -> Self {
+        Self::new(BLOCK_HISTORY_WINDOW)
+    }
+}
+
+// Result of comparing a new block against the recorded history.
+pub struct CanonicalBranch {
+    // Last block shared by the recorded history and the new chain, if one was found in the window.
+    pub common_ancestor: Option<u64>,
+    // Blocks of the new chain above the common ancestor, oldest first.
+    pub blocks: Vec<(u64, H256)>,
+    // Parent of the oldest block in `blocks`.
+    pub parent_hash: H256,
+}
+
+// Walk back from a new block through its parents until a block matching the
+// recorded history is found. Only blocks whose hash differs from history are
+// fetched, so the common case of a block extending the tip costs no RPC calls.
+pub async fn canonical_branch(
+    provider: &RpcProvider,
+    history: &BlockHistory,
+    number: u64,
+    hash: H256,
+    parent_hash: H256,
+) -> Result<CanonicalBranch> {
+    let mut blocks = vec![(number, hash)];
+    let mut cursor_number = number.saturating_sub(1);
+    let mut cursor_hash = parent_hash;
+
+    while let Some(recorded) = history.hash_at(cursor_number) {
+        if recorded == cursor_hash {
+            blocks.reverse();
+            return Ok(CanonicalBranch {
+                common_ancestor: Some(cursor_number),
+                blocks,
+                parent_hash: cursor_hash,
+            });
+        }
+
+        let parent = match provider.get_block(cursor_hash).await? {
+            Some(block) => block.parent_hash,
+            None => break,
+        };
+        blocks.push((cursor_number, cursor_hash));
+
+        if cursor_number == 0 {
+            break;
+        }
+        cursor_number -= 1;
+        cursor_hash = parent;
+    }
+
+    blocks.reverse();
+    Ok(CanonicalBranch { common_ancestor: None, blocks, parent_hash: cursor_hash })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers;
+    use crate::test_rpc::{self, Reply};
+
+    use std::collections::HashMap;
+
+    use tokio::sync::mpsc;
+
+    fn hash(n: u64) -> H256 {
+        H256::from_low_u64_be(n)
+    }
+
+    fn history(blocks: impl IntoIterator<Item = u64>) -> BlockHistory {
+        let mut history = BlockHistory::new(BLOCK_HISTORY_WINDOW);
+        for number in blocks {
+            history.push(number, hash(number));
+        }
+        history
+    }
+
+    // A provider for a network outside the networks table, so its endpoint isn't asked
+    // for a chain ID.
+    fn provider(url: &str) -> RpcProvider {
+        let (tx_logs, _) = mpsc::channel(32);
+        providers::connect("test", &[url.to_string()], tx_logs).unwrap()
+    }
+
+    // Serve `eth_getBlockByHash` for blocks given as hash => (number, parent hash).
+    async fn serve_blocks(blocks: HashMap<H256, (u64, H256)>) -> String {
+        test_rpc::serve(move |_, params| {
+            let block_hash: H256 = serde_json::from_value(params[0].clone()).unwrap();
+            let block = blocks
+                .get(&block_hash)
+                .map(|(number, parent_hash)| test_rpc::block(*number, block_hash, *parent_hash));
+            Reply::Result(block.into())
+        })
+        .await
+    }
+
+    #[test]
+    fn history_keeps_one_branch() {
+        let mut history = history(1..=5);
+        assert_eq!(history.hash_at(3), Some(hash(3)));
+
+        // A block at an existing height replaces it and everything above it
+        history.push(4, hash(104));
+        assert_eq!(history.hash_at(4), Some(hash(104)));
+        assert_eq!(history.hash_at(5), None);
+        assert_eq!(history.hash_at(3), Some(hash(3)));
+    }
+
+    #[test]
+    fn history_drops_the_oldest_block() {
+        let mut history = BlockHistory::new(3);
+        for number in 1..=4 {
+            history.push(number, hash(number));
+        }
+        assert_eq!(history.hash_at(1), None);
+        assert_eq!(history.hash_at(2), Some(hash(2)));
+        assert_eq!(history.hash_at(4), Some(hash(4)));
+    }
+
+    #[test]
+    fn truncate_returns_orphans_oldest_first() {
+        let mut history = history(1..=5);
+        assert_eq!(history.truncate_after(3), vec![(4, hash(4)), (5, hash(5))]);
+        assert_eq!(history.hash_at(4), None);
+        assert!(history.truncate_after(3).is_empty());
+    }
+
+    #[tokio::test]
+    async fn block_extending_the_tip_needs_no_rpc() {
+        // Nothing listens there, so any request would fail
+        let provider = provider("http://127.0.0.1:1");
+        let branch = canonical_branch(&provider, &history(1..=5), 6, hash(6), hash(5)).await;
+        let branch = branch.unwrap();
+        assert_eq!(branch.common_ancestor, Some(5));
+        assert_eq!(branch.blocks, vec![(6, hash(6))]);
+        assert_eq!(branch.parent_hash, hash(5));
+    }
+
+    #[tokio::test]
+    async fn reorg_walks_back_to_the_common_ancestor() {
+        // Blocks 4 and 5 were replaced by 104 and 105, which build on block 3
+        let url =
+            serve_blocks(HashMap::from([(hash(105), (5, hash(104))), (hash(104), (4, hash(3)))]))
+                .await;
+        let provider = provider(&url);
+        let branch =
+            canonical_branch(&provider, &history(1..=5), 6, hash(106), hash(105)).await.unwrap();
+        assert_eq!(branch.common_ancestor, Some(3));
+        assert_eq!(branch.blocks, vec![(4, hash(104)), (5, hash(105)), (6, hash(106))]);
+        assert_eq!(branch.parent_hash, hash(3));
+    }
+
+    #[tokio::test]
+    async fn reorg_deeper_than_the_history() {
+        // The new chain shares no block with the history
+        let url =
+            serve_blocks(HashMap::from([(hash(103), (3, hash(102))), (hash(102), (2, hash(101)))]))
+                .await;
+        let provider = provider(&url);
+        let branch =
+            canonical_branch(&provider, &history(2..=3), 4, hash(104), hash(103)).await.unwrap();
+        assert_eq!(branch.common_ancestor, None);
+        assert_eq!(branch.blocks, vec![(2, hash(102)), (3, hash(103)), (4, hash(104))]);
+        assert_eq!(branch.parent_hash, hash(101));
+    }
+}
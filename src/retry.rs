@@ -0,0 +1,417 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use ethers::core::rand::Rng;
+use ethers::prelude::{HttpClientError, JsonRpcError, ProviderError};
+use tokio::time::sleep;
+
+use crate::error::Error;
+use crate::providers::FailoverError;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorClass {
+    // The provider is throttling us, back off harder before trying again.
+    RateLimit,
+    // The nonce was already used or is too far ahead, the call has to be rebuilt.
+    Nonce,
+    // The contract rejected the call. Retrying the same call will fail the same way.
+    Revert,
+    // Connection problems, timeouts and other transient transport failures.
+    Network,
+    Other,
+}
+
+// Errors that can tell how they should be retried.
+pub trait Classify {
+    fn class(&self) -> ErrorClass;
+}
+
+// JSON-RPC error codes. Providers disagree on most of them, so only the ones with a
+// single meaning are used, the generic server error (-32000) falls back to its message.
+const RPC_EXECUTION_REVERTED: i64 = 3;
+const RPC_LIMIT_EXCEEDED: i64 = -32005;
+const RPC_VM_EXECUTION_ERROR: i64 = -32015;
+const RPC_TOO_MANY_REQUESTS: i64 = 429;
+
+fn classify_rpc_error(error: &JsonRpcError) -> ErrorClass {
+    // Reverts go first, their message may contain anything the contract returned
+    if error.code == RPC_EXECUTION_REVERTED
+        || error.code == RPC_VM_EXECUTION_ERROR
+        || error.as_revert_data().is_some()
+    {
+        return ErrorClass::Revert;
+    }
+    match error.code {
+        RPC_LIMIT_EXCEEDED | RPC_TOO_MANY_REQUESTS => ErrorClass::RateLimit,
+        _ => classify_message(&error.message),
+    }
+}
+
+fn classify_status(status: u16) -> ErrorClass {
+    match status {
+        429 => ErrorClass::RateLimit,
+        408 | 500..=599 => ErrorClass::Network,
+        _ => ErrorClass::Other,
+    }
+}
+
+// Errors of the HTTP client itself. Connecting, sending the request or reading the
+// body failed, unless the server answered with an error status.
+fn classify_transport(status: Option<u16>, malformed: bool) -> ErrorClass {
+    match status {
+        Some(status) => classify_status(status),
+        None if malformed => ErrorClass::Other,
+        None => ErrorClass::Network,
+    }
+}
+
+fn classify_http_error(error: &HttpClientError) -> ErrorClass {
+    match error {
+        HttpClientError::ReqwestError(e) => {
+            classify_transport(e.status().map(|s| s.as_u16()), e.is_decode() || e.is_builder())
+        }
+        HttpClientError::JsonRpcError(e) => classify_rpc_error(e),
+        // The transport drops the HTTP status of responses that aren't JSON-RPC. Those
+        // are error pages of the gateways and rate limiters in front of the node, which
+        // state the status in their body.
+        HttpClientError::SerdeJson { text, .. } => text
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|code| code.parse::<u16>().ok())
+            .find(|code| (400..600).contains(code))
+            .map(classify_status)
+            .unwrap_or(ErrorClass::Network),
+    }
+}
+
+impl Classify for ProviderError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            ProviderError::JsonRpcClientError(e) => {
+                if let Some(response) = e.as_error_response() {
+                    return classify_rpc_error(response);
+                }
+                let error: &(dyn std::error::Error + 'static) = &**e;
+                if let Some(e) = error.downcast_ref::<HttpClientError>() {
+                    classify_http_error(e)
+                } else if let Some(e) = error.downcast_ref::<FailoverError>() {
+                    match e {
+                        FailoverError::Http(e) => classify_http_error(e),
+                        FailoverError::NoVerifiedEndpoints => ErrorClass::Network,
+                        FailoverError::Serde(_) => ErrorClass::Other,
+                    }
+                } else {
+                    ErrorClass::Other
+                }
+            }
+            ProviderError::HTTPError(e) => {
+                classify_transport(e.status().map(|s| s.as_u16()), e.is_decode() || e.is_builder())
+            }
+            ProviderError::CustomError(message) => classify_message(message),
+            _ => ErrorClass::Other,
+        }
+    }
+}
+
+impl Classify for Error {
+    fn class(&self) -> ErrorClass {
+        match self {
+            Error::Provider(e) => e.class(),
+            // Reverts and calls that can't be encoded fail the same way every time
+            Error::Contract(_) => ErrorClass::Revert,
+            Error::Transaction(message) => classify_message(message),
+            Error::Retry(e) => e.class,
+            _ => ErrorClass::Other,
+        }
+    }
+}
+
+// Classify an error that only has a message, e.g. the generic JSON-RPC server error.
+// Reverts are matched first, the revert reason is chosen by the contract and may
+// contain any of the other patterns.
+fn classify_message(message: &str) -> ErrorClass {
+    let message = message.to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|pattern| message.contains(pattern));
+
+    if matches(&["revert", "invalid opcode", "out of gas"]) {
+        ErrorClass::Revert
+    } else if matches(&["nonce too low", "nonce too high", "invalid nonce", "already known"]) {
+        ErrorClass::Nonce
+    } else if matches(&["rate limit", "too many requests", "limit exceeded", "throttl"]) {
+        ErrorClass::RateLimit
+    } else if matches(&["connection", "timed out", "timeout", "broken pipe"]) {
+        ErrorClass::Network
+    } else {
+        ErrorClass::Other
+    }
+}
+
+// How often and how far apart a call is retried. Delays grow exponentially from
+// `base_delay` up to `max_delay`, with a random jitter so many tasks failing at the
+// same time don't retry in lockstep.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub attempts: usize,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: u32,
+    // Fraction of the delay that is randomized, between 0.0 and 1.0.
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    pub fn new(attempts: usize, base_delay: Duration) -> Self {
+        RetryPolicy {
+            attempts: attempts.max(1),
+            base_delay,
+            max_delay: Duration::from_secs(60),
+            multiplier: 2,
+            jitter: 0.25,
+        }
+    }
+
+    // Delay before the given retry (0 based), with jitter applied.
+    pub fn delay(&self, retry: usize, class: ErrorClass) -> Duration {
+        let factor = self.multiplier.saturating_pow(retry.min(16) as u32);
+        let mut delay = self.base_delay.saturating_mul(factor);
+        if class == ErrorClass::RateLimit {
+            delay = delay.saturating_mul(2);
+        }
+        let delay = delay.min(self.max_delay).as_secs_f64();
+        let jitter = delay * self.jitter.clamp(0.0, 1.0);
+        let jittered =
+            delay - jitter + ethers::core::rand::thread_rng().gen_range(0.0..=2.0 * jitter);
+        Duration::from_secs_f64(jittered.max(0.0))
+    }
+
+    pub fn should_retry(&self, class: ErrorClass) -> bool {
+        class != ErrorClass::Revert
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(10, Duration::from_millis(500))
+    }
+}
+
+// The last error of a call that did not succeed, with how many attempts were made.
+#[derive(Debug)]
+pub struct RetryError<E> {
+    pub attempts: usize,
+    pub class: ErrorClass,
+    pub source: E,
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Function did not succeed after {} attempts ({:?}): {}",
+            self.attempts, self.class, self.source
+        )
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for RetryError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+// Call `func` until it succeeds, the error can't be fixed by retrying, or the
+// policy runs out of attempts. `on_error` is called for every failed attempt.
+// The returned future is `Send` whenever `func`'s futures are, so this can be used
+// from spawned tasks.
+pub async fn retry<F, Fut, T, E, L>(
+    policy: &RetryPolicy,
+    mut func: F,
+    mut on_error: L,
+) -> Result<T, RetryError<E>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Classify,
+    L: FnMut(usize, ErrorClass, &E),
+{
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let error = match func().await {
+            Ok(result) => return Ok(result),
+            Err(e) => e,
+        };
+
+        let class = error.class();
+        on_error(attempt, class, &error);
+        if attempt >= policy.attempts || !policy.should_retry(class) {
+            return Err(RetryError { attempts: attempt, class, source: error });
+        }
+
+        sleep(policy.delay(attempt - 1, class)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use ethers::contract::ContractError;
+    use ethers::types::Bytes;
+
+    fn rpc_error(code: i64, message: &str) -> ProviderError {
+        HttpClientError::JsonRpcError(JsonRpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        })
+        .into()
+    }
+
+    fn gateway_error(body: &str) -> ProviderError {
+        HttpClientError::SerdeJson {
+            err: serde_json::from_str::<serde_json::Value>(body).unwrap_err(),
+            text: body.to_string(),
+        }
+        .into()
+    }
+
+    fn policy(attempts: usize) -> RetryPolicy {
+        RetryPolicy { jitter: 0.0, ..RetryPolicy::new(attempts, Duration::ZERO) }
+    }
+
+    #[test]
+    fn reverts_are_classified_before_anything_else() {
+        assert_eq!(rpc_error(3, "execution reverted: 429").class(), ErrorClass::Revert);
+        assert_eq!(rpc_error(-32015, "VM execution error").class(), ErrorClass::Revert);
+        assert_eq!(
+            rpc_error(-32000, "execution reverted: rate limit exceeded").class(),
+            ErrorClass::Revert
+        );
+        let reverted = Error::Contract(ContractError::Revert(Bytes::from_static(b"429")));
+        assert_eq!(reverted.class(), ErrorClass::Revert);
+    }
+
+    #[test]
+    fn rpc_errors_are_classified_by_code() {
+        assert_eq!(rpc_error(-32005, "request failed").class(), ErrorClass::RateLimit);
+        assert_eq!(rpc_error(429, "slow down").class(), ErrorClass::RateLimit);
+        assert_eq!(rpc_error(-32000, "nonce too low").class(), ErrorClass::Nonce);
+        assert_eq!(rpc_error(-32602, "invalid argument 0: eof").class(), ErrorClass::Other);
+    }
+
+    #[test]
+    fn http_errors_are_classified_by_status() {
+        assert_eq!(gateway_error("429 Too Many Requests").class(), ErrorClass::RateLimit);
+        assert_eq!(gateway_error("<h1>502 Bad Gateway</h1>").class(), ErrorClass::Network);
+        assert_eq!(gateway_error("<h1>404 Not Found</h1>").class(), ErrorClass::Other);
+        assert_eq!(gateway_error("upstream unavailable").class(), ErrorClass::Network);
+        assert_eq!(
+            ProviderError::from(FailoverError::NoVerifiedEndpoints).class(),
+            ErrorClass::Network
+        );
+    }
+
+    #[test]
+    fn crate_errors_keep_the_class_of_their_source() {
+        assert_eq!(Error::Provider(rpc_error(-32005, "")).class(), ErrorClass::RateLimit);
+        let retried = Error::Retry(Box::new(RetryError {
+            attempts: 3,
+            class: ErrorClass::Network,
+            source: Error::NotFound("block".to_string()),
+        }));
+        assert_eq!(retried.class(), ErrorClass::Network);
+        assert_eq!(Error::Config("missing".to_string()).class(), ErrorClass::Other);
+    }
+
+    #[test]
+    fn delays_grow_exponentially_up_to_the_maximum() {
+        let policy = RetryPolicy { max_delay: Duration::from_secs(3), ..policy(10) };
+        let policy = RetryPolicy { base_delay: Duration::from_millis(500), ..policy };
+        assert_eq!(policy.delay(0, ErrorClass::Network), Duration::from_millis(500));
+        assert_eq!(policy.delay(2, ErrorClass::Network), Duration::from_secs(2));
+        assert_eq!(policy.delay(1, ErrorClass::RateLimit), Duration::from_secs(2));
+        assert_eq!(policy.delay(5, ErrorClass::Network), Duration::from_secs(3));
+        assert_eq!(policy.delay(usize::MAX, ErrorClass::RateLimit), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn jitter_stays_within_its_fraction_of_the_delay() {
+        let policy = RetryPolicy { jitter: 0.25, ..RetryPolicy::new(3, Duration::from_secs(1)) };
+        for _ in 0..100 {
+            let delay = policy.delay(0, ErrorClass::Network);
+            assert!(delay >= Duration::from_millis(750) && delay <= Duration::from_millis(1250));
+        }
+    }
+
+    #[test]
+    fn only_reverts_are_not_retried() {
+        let policy = RetryPolicy::default();
+        assert!(!policy.should_retry(ErrorClass::Revert));
+        for class in
+            [ErrorClass::RateLimit, ErrorClass::Nonce, ErrorClass::Network, ErrorClass::Other]
+        {
+            assert!(policy.should_retry(class));
+        }
+        assert_eq!(RetryPolicy::new(0, Duration::ZERO).attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn retry_stops_after_the_last_attempt() {
+        let calls = Cell::new(0);
+        let failed = Cell::new(0);
+        let result: std::result::Result<(), _> = retry(
+            &policy(3),
+            || {
+                calls.set(calls.get() + 1);
+                async { Err(rpc_error(-32005, "limit exceeded")) }
+            },
+            |attempt, class, _| {
+                assert_eq!(class, ErrorClass::RateLimit);
+                failed.set(attempt);
+            },
+        )
+        .await;
+
+        let error = result.unwrap_err();
+        assert_eq!((error.attempts, error.class), (3, ErrorClass::RateLimit));
+        assert_eq!((calls.get(), failed.get()), (3, 3));
+    }
+
+    #[tokio::test]
+    async fn retry_gives_up_on_reverts_and_returns_successes() {
+        let calls = Cell::new(0);
+        let result: std::result::Result<(), _> = retry(
+            &policy(5),
+            || {
+                calls.set(calls.get() + 1);
+                async { Err(rpc_error(3, "execution reverted")) }
+            },
+            |_, _, _| {},
+        )
+        .await;
+        assert_eq!(result.unwrap_err().attempts, 1);
+        assert_eq!(calls.get(), 1);
+
+        let calls = Cell::new(0);
+        let result = retry(
+            &policy(5),
+            || {
+                calls.set(calls.get() + 1);
+                let call = calls.get();
+                async move {
+                    if call < 3 {
+                        Err(gateway_error("503 Service Unavailable"))
+                    } else {
+                        Ok(call)
+                    }
+                }
+            },
+            |_, _, _| {},
+        )
+        .await;
+        assert_eq!(result.unwrap(), 3);
+    }
+}
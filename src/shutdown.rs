@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+// How long shutdown waits for in-flight blocks before giving up on them
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Wait for Ctrl+C or, on unix, SIGTERM. Returns the name of the signal.
+pub async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = sigterm.recv() => "SIGTERM",
+            },
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl+C"
+    }
+}
@@ -0,0 +1,516 @@
+use crate::error::{Error, Result};
+use crate::health::{Health, ProviderStatus};
+use crate::logging::{LogContext, LogLevel};
+use crate::metrics::Metrics;
+use crate::providers::RpcProvider;
+use crate::reorg::{self, canonical_branch, BlockHistory};
+use crate::retry::{self, Classify, RetryPolicy};
+use crate::{BlockJob, LogMessage, NetworkMonitor};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethers::prelude::*;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+// Attempts made to fetch a single block before the stream is restarted
+const BLOCK_FETCH_ATTEMPTS: usize = 3;
+
+// Watches the blocks of a single network and turns them into block jobs. Any
+// error restarts the block stream after a backoff; the subscription only stops
+// once the block job receiver has been dropped, i.e. on shutdown.
+pub struct BlockSubscription {
+    network: String,
+    provider: Arc<RpcProvider>,
+    // Last block a job was sent for, used to catch up on blocks that were missed.
+    last_sent: Option<u64>,
+    current_block_height: Arc<Mutex<HashMap<String, u64>>>,
+    block_jobs: Arc<Mutex<HashMap<String, Vec<BlockJob>>>>,
+    metrics: Arc<Metrics>,
+    health: Arc<Health>,
+    tx_logs: mpsc::Sender<LogMessage>,
+    tx_blocks: mpsc::Sender<BlockJob>,
+    // Rolling window of recent block hashes, used to detect reorgs.
+    history: BlockHistory,
+    // Restarts since the last block that was processed successfully.
+    restarts: usize,
+    shutdown: CancellationToken,
+}
+
+impl BlockSubscription {
+    // Create a subscription for `network`, sharing the block state of the monitor and
+    // resuming after its checkpoint.
+    pub fn new(
+        monitor: &NetworkMonitor,
+        network: &str,
+        provider: Arc<RpcProvider>,
+        tx_logs: mpsc::Sender<LogMessage>,
+        tx_blocks: mpsc::Sender<BlockJob>,
+    ) -> Self {
+        BlockSubscription {
+            network: network.to_string(),
+            provider,
+            last_sent: monitor.checkpoints.get(network),
+            current_block_height: monitor.current_block_height.clone(),
+            block_jobs: monitor.block_jobs.clone(),
+            metrics: monitor.metrics.clone(),
+            health: monitor.health.clone(),
+            tx_logs,
+            tx_blocks,
+            history: BlockHistory::default(),
+            restarts: 0,
+            shutdown: monitor.shutdown.clone(),
+        }
+    }
+
+    // Log context of this subscription's network.
+    fn context(&self) -> LogContext {
+        LogContext::network(&self.network)
+    }
+
+    async fn log(&self, level: LogLevel, context: LogContext, msg: String) {
+        let _ = self.tx_logs.send(LogMessage { level, context, msg }).await;
+    }
+
+    // Supervise the block stream, restarting it whenever it fails or ends, until
+    // shutdown. Dropping the subscription closes the block job channel.
+    pub async fn run(mut self) {
+        let policy = RetryPolicy::default();
+        let shutdown = self.shutdown.clone();
+        loop {
+            let result = tokio::select! {
+                _ = shutdown.cancelled() => return,
+                result = self.watch() => result,
+            };
+            let class = match result {
+                Ok(()) if self.tx_blocks.is_closed() => return,
+                Ok(()) => {
+                    self.log(
+                        LogLevel::Warn,
+                        self.context().tag(Some("subscription")),
+                        "Block stream ended".to_string(),
+                    )
+                    .await;
+                    retry::ErrorClass::Network
+                }
+                Err(e) => {
+                    self.metrics.record_error(&self.network, e.kind());
+                    self.log(
+                        LogLevel::Error,
+                        self.context().tag(Some("subscription")),
+                        format!("Block stream failed: {}", e),
+                    )
+                    .await;
+                    e.class()
+                }
+            };
+            if self.tx_blocks.is_closed() {
+                return;
+            }
+            self.health.set_status(&self.network, ProviderStatus::Disconnected);
+
+            let delay = policy.delay(self.restarts, class);
+            self.restarts += 1;
+            self.log(
+                LogLevel::Warn,
+                self.context().tag(Some("subscription")),
+                format!(
+                    "Restarting block stream in {:.1}s (restart {})",
+                    delay.as_secs_f64(),
+                    self.restarts
+                ),
+            )
+            .await;
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                _ = sleep(delay) => {}
+            }
+        }
+    }
+
+    // Poll the head of the chain and handle every new head block, until a request fails
+    // or the block job receiver is gone. Polling `eth_blockNumber` instead of a block
+    // filter surfaces RPC failures, and keeps working when the provider fails over to
+    // another endpoint.
+    async fn watch(&mut self) -> Result<()> {
+        let provider = self.provider.clone();
+        let fetch_policy = RetryPolicy::new(BLOCK_FETCH_ATTEMPTS, Duration::from_millis(500));
+        let mut last_head = None;
+
+        loop {
+            let head = self
+                .metrics
+                .time_rpc(&self.network, "eth_blockNumber", provider.get_block_number())
+                .await?
+                .as_u64();
+            self.health.set_status(&self.network, ProviderStatus::Connected);
+
+            // A lower head means the chain reorged to a shorter branch, or the endpoint
+            // that took over lags behind. Either way it's compared against the history.
+            if last_head != Some(head) {
+                // Fetch the head block, retrying transient failures.
+                let tx_logs = self.tx_logs.clone();
+                let context = self.context().tag(Some("subscription"));
+                let block_opt = retry::retry(
+                    &fetch_policy,
+                    || {
+                        self.metrics.time_rpc(
+                            &self.network,
+                            "eth_getBlockByNumber",
+                            provider.get_block(head),
+                        )
+                    },
+                    |attempt, class, e| {
+                        let _ = tx_logs.try_send(LogMessage {
+                            level: LogLevel::Warn,
+                            context: context.clone(),
+                            msg: format!(
+                                "Attempt {}/{} to get block {} failed ({:?}): {}",
+                                attempt, BLOCK_FETCH_ATTEMPTS, head, class, e
+                            ),
+                        });
+                    },
+                )
+                .await
+                .map_err(Error::from)?;
+
+                match block_opt {
+                    Some(Block { hash: Some(hash), parent_hash, .. }) => {
+                        if !self.handle_block(head, hash, parent_hash).await? {
+                            return Ok(());
+                        }
+                        last_head = Some(head);
+                        self.restarts = 0;
+                    }
+                    // Tried again on the next poll
+                    _ => {
+                        self.log(
+                            LogLevel::Warn,
+                            self.context().tag(Some("subscription")),
+                            format!("Block {} was not returned by the provider", head),
+                        )
+                        .await;
+                    }
+                }
+            }
+
+            sleep(provider.get_interval()).await;
+        }
+    }
+
+    // Record a new block and send block jobs for it, rolling back orphaned blocks
+    // on a reorg. Returns false once the block job receiver is gone.
+    async fn handle_block(
+        &mut self,
+        current_block_u64: u64,
+        new_block_hash: H256,
+        parent_hash: H256,
+    ) -> Result<bool> {
+        // Skip blocks we have already seen.
+        if self.history.hash_at(current_block_u64) == Some(new_block_hash) {
+            return Ok(true);
+        }
+
+        // Find where the new block joins the chain we have already processed.
+        let branch = canonical_branch(
+            &self.provider,
+            &self.history,
+            current_block_u64,
+            new_block_hash,
+            parent_hash,
+        )
+        .await?;
+
+        // Anything recorded above the common ancestor belongs to an orphaned branch.
+        let first_canonical = branch.blocks[0].0;
+        let ancestor = branch.common_ancestor.unwrap_or(first_canonical.saturating_sub(1));
+        let orphaned = self.history.truncate_after(ancestor);
+
+        if !orphaned.is_empty() {
+            if branch.common_ancestor.is_none() {
+                let log_msg = format!(
+                    "Reorg at block {} is deeper than the {} block history window",
+                    first_canonical,
+                    reorg::BLOCK_HISTORY_WINDOW
+                );
+                self.log(
+                    LogLevel::Warn,
+                    self.context().block(first_canonical).tag(Some("reorg")),
+                    log_msg,
+                )
+                .await;
+            }
+
+            // Drop jobs derived from the orphaned blocks so they are not acted upon.
+            let (rolled_back, rolled_back_final) = {
+                let mut jobs = self.block_jobs.lock().await;
+                let network_jobs = jobs.entry(self.network.clone()).or_default();
+                let before = network_jobs.len();
+                let finalized =
+                    network_jobs.iter().filter(|job| job.block > ancestor && job.finalized).count();
+                network_jobs.retain(|job| job.block <= ancestor);
+                (before - network_jobs.len(), finalized)
+            };
+
+            if rolled_back_final > 0 {
+                let log_msg = format!(
+                    "Reorg rolled back {} blocks that were already considered final, the confirmation depth for this network is too low",
+                    rolled_back_final
+                );
+                self.log(
+                    LogLevel::Warn,
+                    self.context().block(first_canonical).tag(Some("reorg")),
+                    log_msg,
+                )
+                .await;
+            }
+
+            let log_msg = format!(
+                "Chain reorganization detected. Orphaned blocks [{}..{}], rolled back {} block jobs, re-processing from block {}",
+                orphaned[0].0,
+                orphaned[orphaned.len() - 1].0,
+                rolled_back,
+                first_canonical
+            );
+            self.log(
+                LogLevel::Warn,
+                self.context().block(first_canonical).tag(Some("reorg")),
+                log_msg,
+            )
+            .await;
+        }
+
+        // Update the current block height before sending any job, so the jobs are
+        // checked for finality against the new head.
+        self.current_block_height.lock().await.insert(self.network.clone(), current_block_u64);
+        self.metrics.set_head_block(&self.network, current_block_u64);
+        self.health.block_advanced(&self.network);
+
+        // Catch up on blocks missed while the connection was down or the process was not
+        // running. These are queued before the new blocks so everything stays in order.
+        if let Some(last_sent) = self.last_sent {
+            if last_sent + 1 < first_canonical {
+                let log_msg = format!(
+                    "Resuming previously dropped connection, gotta do some catching up. Blocks: {} to {}",
+                    last_sent + 1,
+                    first_canonical - 1
+                );
+                self.log(LogLevel::Info, self.context(), log_msg).await;
+
+                // Record the hashes of caught up blocks too, so a reorg reaching into them
+                // is detected. Older blocks would fall out of the history window anyway.
+                let window_start =
+                    first_canonical.saturating_sub(reorg::BLOCK_HISTORY_WINDOW as u64);
+                for number in last_sent + 1..first_canonical {
+                    let mut block_job = BlockJob {
+                        network: self.network.clone(),
+                        block: number,
+                        hash: None,
+                        parent_hash: None,
+                        finalized: false,
+                        drop_logs: Vec::new(),
+                        deployments: Vec::new(),
+                    };
+                    if number >= window_start {
+                        let block = self
+                            .metrics
+                            .time_rpc(
+                                &self.network,
+                                "eth_getBlockByNumber",
+                                self.provider.get_block(number),
+                            )
+                            .await?;
+                        if let Some(block) = block {
+                            if let Some(hash) = block.hash {
+                                self.history.push(number, hash);
+                            }
+                            block_job.hash = block.hash;
+                            block_job.parent_hash = Some(block.parent_hash);
+                        }
+                    }
+
+                    if self.tx_blocks.send(block_job).await.is_err() {
+                        return Ok(false);
+                    }
+                    // A failure further on resumes the catch up after this block
+                    self.last_sent = Some(number);
+                }
+            }
+        }
+
+        // Record and (re-)process every canonical block above the common ancestor.
+        let mut parent = branch.parent_hash;
+        for (number, hash) in branch.blocks {
+            self.history.push(number, hash);
+
+            let block_job = BlockJob {
+                network: self.network.clone(),
+                block: number,
+                hash: Some(hash),
+                parent_hash: Some(parent),
+                finalized: false,
+                drop_logs: Vec::new(),
+                deployments: Vec::new(),
+            };
+            if self.tx_blocks.send(block_job).await.is_err() {
+                return Ok(false);
+            }
+            parent = hash;
+        }
+        self.last_sent = Some(current_block_u64);
+
+        // Log that a new block has been mined.
+        let log_msg =
+            format!("A new block has been mined. New block height is [{}]", current_block_u64);
+        self.log(LogLevel::Info, self.context().block(current_block_u64), log_msg).await;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers;
+    use crate::test_rpc::{self, Reply};
+
+    use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+    use serde_json::Value;
+
+    fn hash(n: u64) -> H256 {
+        H256::from_low_u64_be(n)
+    }
+
+    // Serve a chain whose block n has hash n, up to the head returned by `head`.
+    fn chain(method: &str, params: &Value, head: u64) -> Reply {
+        let number = match method {
+            "eth_blockNumber" => return Reply::Result(serde_json::json!(U64::from(head))),
+            "eth_getBlockByNumber" => serde_json::from_value::<U64>(params[0].clone()).ok(),
+            "eth_getBlockByHash" => serde_json::from_value::<H256>(params[0].clone())
+                .ok()
+                .map(|hash| U64::from(hash.to_low_u64_be())),
+            _ => return Reply::Error(-32601, format!("{} is not supported", method)),
+        };
+        let block = number
+            .map(|number| number.as_u64())
+            .filter(|number| *number <= head)
+            .map(|number| test_rpc::block(number, hash(number), hash(number.saturating_sub(1))));
+        Reply::Result(block.into())
+    }
+
+    async fn next_block(rx_blocks: &mut mpsc::Receiver<BlockJob>) -> u64 {
+        let job = tokio::time::timeout(Duration::from_secs(10), rx_blocks.recv())
+            .await
+            .expect("blocks should keep arriving")
+            .unwrap();
+        job.block
+    }
+
+    fn subscription(
+        urls: &[String],
+        last_sent: Option<u64>,
+        tx_blocks: mpsc::Sender<BlockJob>,
+    ) -> BlockSubscription {
+        let (tx_logs, _) = mpsc::channel(1024);
+        BlockSubscription {
+            network: "test".to_string(),
+            provider: Arc::new(providers::connect("test", urls, tx_logs.clone()).unwrap()),
+            last_sent,
+            current_block_height: Arc::default(),
+            block_jobs: Arc::default(),
+            metrics: Arc::default(),
+            health: Arc::default(),
+            tx_logs,
+            tx_blocks,
+            history: BlockHistory::default(),
+            restarts: 0,
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn watch_fails_when_the_node_cannot_be_reached() {
+        let url = test_rpc::serve(|_, _| Reply::Hangup).await;
+        let (tx_blocks, _rx_blocks) = mpsc::channel(16);
+        let mut subscription = subscription(&[url], None, tx_blocks);
+
+        let result = tokio::time::timeout(Duration::from_secs(10), subscription.watch()).await;
+        assert!(result.expect("watch should not hang").is_err());
+    }
+
+    #[tokio::test]
+    async fn head_is_updated_before_catching_up() {
+        let url = test_rpc::serve(|method, params| chain(method, params, 5)).await;
+        let (tx_blocks, mut rx_blocks) = mpsc::channel(1);
+        let mut subscription = subscription(&[url], Some(2), tx_blocks);
+        let heights = subscription.current_block_height.clone();
+
+        tokio::spawn(async move { subscription.handle_block(5, hash(5), hash(4)).await });
+
+        let first = rx_blocks.recv().await.unwrap();
+        assert_eq!(first.block, 3);
+        assert_eq!(heights.lock().await.get("test"), Some(&5));
+        assert_eq!((first.hash, first.parent_hash), (Some(hash(3)), Some(hash(2))));
+
+        let rest: Vec<_> = [rx_blocks.recv().await.unwrap(), rx_blocks.recv().await.unwrap()]
+            .into_iter()
+            .map(|job| (job.block, job.hash, job.parent_hash))
+            .collect();
+        assert_eq!(rest, [(4, Some(hash(4)), Some(hash(3))), (5, Some(hash(5)), Some(hash(4)))]);
+    }
+
+    #[tokio::test]
+    async fn blocks_keep_arriving_after_a_failover() {
+        // Both endpoints serve the same chain, which grows by a block on every poll
+        let head = Arc::new(AtomicU64::new(1));
+        let primary_up = Arc::new(AtomicBool::new(true));
+        let backup_requests = Arc::new(AtomicUsize::new(0));
+
+        let (chain_head, up) = (head.clone(), primary_up.clone());
+        let primary = test_rpc::serve(move |method, params| {
+            if !up.load(Ordering::SeqCst) {
+                return Reply::Hangup;
+            }
+            if method == "eth_blockNumber" {
+                chain_head.fetch_add(1, Ordering::SeqCst);
+            }
+            chain(method, params, chain_head.load(Ordering::SeqCst))
+        })
+        .await;
+        let (chain_head, requests) = (head.clone(), backup_requests.clone());
+        let backup = test_rpc::serve(move |method, params| {
+            requests.fetch_add(1, Ordering::SeqCst);
+            if method == "eth_blockNumber" {
+                chain_head.fetch_add(1, Ordering::SeqCst);
+            }
+            chain(method, params, chain_head.load(Ordering::SeqCst))
+        })
+        .await;
+
+        let (tx_blocks, mut rx_blocks) = mpsc::channel(64);
+        let subscription = subscription(&[primary, backup], None, tx_blocks);
+        let shutdown = subscription.shutdown.clone();
+        tokio::spawn(subscription.run());
+
+        let mut last = None;
+        while last < Some(3) {
+            last = Some(next_block(&mut rx_blocks).await);
+        }
+        assert_eq!(backup_requests.load(Ordering::SeqCst), 0);
+
+        primary_up.store(false, Ordering::SeqCst);
+        let switched_at = last.unwrap();
+        while last < Some(switched_at + 3) {
+            let block = next_block(&mut rx_blocks).await;
+            // No block is skipped on the way
+            assert_eq!(Some(block), last.map(|last| last + 1));
+            last = Some(block);
+        }
+        assert!(backup_requests.load(Ordering::SeqCst) > 0);
+        shutdown.cancel();
+    }
+}
@@ -0,0 +1,94 @@
+// A fake JSON-RPC node for tests, answering requests over plain HTTP/1.1.
+
+use std::sync::Arc;
+
+use ethers::types::{Address, H256, U64};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+pub enum Reply {
+    Result(Value),
+    Error(i64, String),
+    // Close the connection without answering, as an endpoint that went down.
+    Hangup,
+}
+
+// Serve requests with `handler`, called with the method and params of every request.
+// Returns the URL of the node.
+pub async fn serve<F>(handler: F) -> String
+where
+    F: Fn(&str, &Value) -> Reply + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                loop {
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let line = line.trim().to_lowercase();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some(length) = line.strip_prefix("content-length:") {
+                            content_length = length.trim().parse().unwrap();
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    stream.read_exact(&mut body).await.unwrap();
+                    let request: Value = serde_json::from_slice(&body).unwrap();
+                    let method = request["method"].as_str().unwrap_or_default();
+                    let response = match handler(method, &request["params"]) {
+                        Reply::Result(result) => {
+                            json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                        }
+                        Reply::Error(code, message) => json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "error": { "code": code, "message": message },
+                        }),
+                        Reply::Hangup => return,
+                    }
+                    .to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                        response.len(),
+                        response
+                    );
+                    stream.get_mut().write_all(response.as_bytes()).await.unwrap();
+                }
+            });
+        }
+    });
+    url
+}
+
+// A block without transactions, as returned by `eth_getBlockBy*`.
+pub fn block(number: u64, hash: H256, parent_hash: H256) -> Value {
+    json!({
+        "hash": hash,
+        "parentHash": parent_hash,
+        "sha3Uncles": H256::zero(),
+        "miner": Address::zero(),
+        "stateRoot": H256::zero(),
+        "transactionsRoot": H256::zero(),
+        "receiptsRoot": H256::zero(),
+        "number": U64::from(number),
+        "gasUsed": "0x0",
+        "gasLimit": "0x0",
+        "extraData": "0x",
+        "timestamp": "0x0",
+        "difficulty": "0x0",
+        "uncles": [],
+        "transactions": [],
+    })
+}
@@ -0,0 +1,71 @@
+use crate::config::{Config, GasPolicy};
+use crate::error::{Error, Result};
+use crate::providers::RpcProvider;
+
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::format_units;
+
+// The wallet commands send transactions from. Unlike the monitor, which only needs
+// an address, these commands can't do anything without a signer.
+pub fn signer(config: &Config) -> Result<LocalWallet> {
+    config.wallet.signer()?.ok_or_else(|| {
+        Error::Config("This command needs a signing wallet, set WALLET_SOURCE".to_string())
+    })
+}
+
+// Gas price to pay on a network: the provider's estimate with the multiplier of the
+// gas policy applied, refusing anything above the policy's maximum.
+pub async fn gas_price(provider: &RpcProvider, policy: &GasPolicy) -> Result<U256> {
+    let estimate = provider.get_gas_price().await?;
+    let price = estimate * U256::from((policy.gas_price_multiplier * 1000.0) as u64) / 1000;
+    if let Some(max_gwei) = policy.max_gas_price_gwei {
+        let max = U256::from(max_gwei) * U256::exp10(9);
+        if price > max {
+            return Err(Error::Transaction(format!(
+                "Gas price {} gwei is above the maximum of {} gwei",
+                format_units(price, "gwei").unwrap_or_default(),
+                max_gwei
+            )));
+        }
+    }
+    Ok(price)
+}
+
+// Sign a transaction with `wallet` and wait for it to be mined. The nonce and gas
+// limit come from the provider, the gas price from the gas policy.
+pub async fn send(
+    provider: &RpcProvider,
+    wallet: &LocalWallet,
+    mut tx: TypedTransaction,
+    policy: &GasPolicy,
+) -> Result<TransactionReceipt> {
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let wallet = wallet.clone().with_chain_id(chain_id);
+    tx.set_from(wallet.address());
+    tx.set_chain_id(chain_id);
+    tx.set_gas_price(gas_price(provider, policy).await?);
+    provider.fill_transaction(&mut tx, None).await.map_err(|e| {
+        // Estimating gas runs the transaction, so this is where most reverts show up
+        Error::Transaction(format!("Failed to prepare transaction: {}", e))
+    })?;
+
+    let signature = wallet
+        .sign_transaction(&tx)
+        .await
+        .map_err(|e| Error::Transaction(format!("Failed to sign transaction: {}", e)))?;
+    let pending = provider
+        .send_raw_transaction(tx.rlp_signed(&signature))
+        .await
+        .map_err(|e| Error::Transaction(format!("Failed to send transaction: {}", e)))?;
+    let hash = pending.tx_hash();
+    println!("Sent {:?}", hash);
+
+    let receipt = pending.await?.ok_or_else(|| {
+        Error::Transaction(format!("Transaction {:?} was dropped from the mempool", hash))
+    })?;
+    if receipt.status != Some(U64::from(1)) {
+        return Err(Error::Transaction(format!("Transaction {:?} reverted", hash)));
+    }
+    Ok(receipt)
+}
@@ -1,4 +1,7 @@
-use ethers::types::{Log, TransactionReceipt, TransactionRequest};
+use crate::events::DecodedEvent;
+use ethers::abi::{ParamType, Token};
+use ethers::types::{Log, Transaction, TransactionReceipt};
+use ethers::utils::{hex, id, keccak256};
 
 pub struct LogsParams {
     pub network: String,
@@ -10,10 +13,39 @@ pub struct LogsParams {
     pub interval: Option<u64>,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TransactionType {
+    Unknown,
+    Erc20,
+    Erc721,
+    Deploy,
+}
+
 pub struct InterestingTransaction {
     pub bloom_id: String,
-    pub transaction: TransactionRequest,
+    pub transaction: Transaction,
     pub receipt: Option<TransactionReceipt>,
     pub log: Option<Log>,
     pub all_logs: Option<Vec<Log>>,
+    pub events: Vec<DecodedEvent>,
+    pub transaction_type: TransactionType,
+}
+
+impl InterestingTransaction {
+    // Hash of the operator job this transaction is about. Operator events carry it,
+    // otherwise it's derived from the payload of an `executeJob(bytes)` call.
+    pub fn job_hash(&self) -> Option<String> {
+        if let Some(job_hash) = self.events.iter().find_map(DecodedEvent::job_hash) {
+            return Some(job_hash.to_string());
+        }
+
+        let input = self.transaction.input.as_ref();
+        if input.len() < 4 || input[..4] != id("executeJob(bytes)") {
+            return None;
+        }
+        match ethers::abi::decode(&[ParamType::Bytes], &input[4..]).ok()?.pop()? {
+            Token::Bytes(payload) => Some(format!("0x{}", hex::encode(keccak256(payload)))),
+            _ => None,
+        }
+    }
 }